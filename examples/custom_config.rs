@@ -32,6 +32,7 @@ fn main() {
         normalize_whitespace: false,
         remove_blank_lines: false,
         trim_lines: false,
+        ..Default::default()
     };
     let result3 = remove_citations_with_config(input, config3);
     println!("{}", result3);
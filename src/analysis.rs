@@ -0,0 +1,228 @@
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::{self, BlockKind};
+use crate::citations::{self, CitationKind, Resolver};
+use crate::convert;
+use crate::patterns::Patterns;
+use crate::position::LineIndex;
+
+/// Structured view of the citations in a document
+///
+/// Returned by [`CitationRemover::analyze`](crate::CitationRemover::analyze) and
+/// [`extract_citations`](crate::extract_citations). Inline citations are linked
+/// to the sources they refer to and vice versa.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CitationAnalysis {
+    /// Inline citation markers in document order
+    pub citations: Vec<Citation>,
+    /// Reference definitions and bibliography entries in document order
+    pub sources: Vec<Source>,
+}
+
+/// An inline citation marker such as `[1]`, `[^1_2]` or `【3†source】`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Citation {
+    pub kind: CitationKind,
+    /// Identifier inside the marker (`1`, `1_2`, `note`)
+    pub id: String,
+    /// Prefix of a named citation (`source` in `[source:1]`)
+    pub prefix: Option<String>,
+    /// Byte range of the marker in the input
+    pub range: Range<usize>,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// Index into [`CitationAnalysis::sources`], `None` if unresolved
+    pub source: Option<usize>,
+}
+
+impl Citation {
+    /// Whether the marker refers to a source in the document
+    pub fn is_resolved(&self) -> bool {
+        self.source.is_some()
+    }
+}
+
+/// Syntax of a source definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum SourceKind {
+    /// `[1]: https://example.com "Title"` or `[1] https://example.com`
+    LinkDefinition,
+    /// `[^1]: Text or https://example.com`
+    Footnote,
+    /// `[1](https://example.com)`
+    LinkLine,
+    /// `[1] Author, A. (2024). Title.`
+    Entry,
+}
+
+impl SourceKind {
+    /// Stable kebab-case identifier, e.g. `link-definition`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LinkDefinition => "link-definition",
+            Self::Footnote => "footnote",
+            Self::LinkLine => "link-line",
+            Self::Entry => "entry",
+        }
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A reference definition or bibliography entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Source {
+    pub kind: SourceKind,
+    /// Label without brackets or caret (`1`, `1_2`, `note`)
+    pub label: String,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// Full definition text, including continuation lines
+    pub raw: String,
+    /// Byte range of the definition block in the input
+    pub range: Range<usize>,
+    /// 1-based line number of the first line
+    pub line: usize,
+    /// Indices into [`CitationAnalysis::citations`] of markers referring to this source
+    pub citations: Vec<usize>,
+}
+
+impl Source {
+    /// Whether any inline citation refers to this source
+    pub fn is_used(&self) -> bool {
+        !self.citations.is_empty()
+    }
+
+    /// Lowercase host of the URL without a leading `www.`, e.g. `reuters.com`
+    pub fn domain(&self) -> Option<String> {
+        self.url
+            .as_deref()
+            .map(|url| convert::domain(&url.to_ascii_lowercase()))
+            .filter(|domain| !domain.is_empty())
+    }
+}
+
+impl CitationAnalysis {
+    /// Inline citations without a matching source
+    pub fn unresolved(&self) -> impl Iterator<Item = &Citation> {
+        self.citations.iter().filter(|c| !c.is_resolved())
+    }
+
+    /// Sources no inline citation refers to
+    pub fn unused(&self) -> impl Iterator<Item = &Source> {
+        self.sources.iter().filter(|s| !s.is_used())
+    }
+
+    /// The source an inline citation refers to
+    pub fn source_of(&self, citation: &Citation) -> Option<&Source> {
+        citation.source.and_then(|i| self.sources.get(i))
+    }
+}
+
+/// Parse all inline citations and sources in `text`
+pub(crate) fn analyze(text: &str, patterns: &Patterns) -> CitationAnalysis {
+    let blocks = blocks::scan(text, patterns);
+    let definitions = citations::definitions(text, &blocks);
+    let markers = citations::markers(text, patterns, &blocks, &blocks::protected_ranges(text));
+    let resolver = Resolver::new(&definitions);
+    let index = LineIndex::new(text);
+
+    let mut sources: Vec<Source> = definitions
+        .iter()
+        .map(|definition| Source {
+            kind: match definition.kind {
+                BlockKind::Footnote => SourceKind::Footnote,
+                BlockKind::LinkLine => SourceKind::LinkLine,
+                BlockKind::Entry => SourceKind::Entry,
+                _ => SourceKind::LinkDefinition,
+            },
+            label: definition.label.clone(),
+            url: definition.url.clone(),
+            title: definition.title.clone(),
+            raw: text[definition.range.clone()]
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            range: definition.range.clone(),
+            line: index.position(text, definition.range.start).0,
+            citations: Vec::new(),
+        })
+        .collect();
+
+    let citations = markers
+        .into_iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            let source = resolver.resolve(&marker);
+            if let Some(source) = source {
+                sources[source].citations.push(i);
+            }
+            let (line, column) = index.position(text, marker.range.start);
+            Citation {
+                kind: marker.kind,
+                id: marker.id,
+                prefix: marker.prefix,
+                range: marker.range,
+                line,
+                column,
+                source,
+            }
+        })
+        .collect();
+
+    CitationAnalysis { citations, sources }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_links_citations_and_sources() {
+        let text = "Fact[1] and【2†source】.\n\n[1]: https://a.com \"A\"\n[3]: https://c.com";
+        let analysis = analyze(text, Patterns::get());
+
+        assert_eq!(analysis.citations.len(), 2);
+        let first = &analysis.citations[0];
+        assert_eq!(
+            (first.kind, first.line, first.column),
+            (CitationKind::Numeric, 1, 5)
+        );
+        assert_eq!(first.range, 4..7);
+        assert_eq!(
+            analysis.source_of(first).map(|s| s.label.as_str()),
+            Some("1")
+        );
+
+        let unresolved: Vec<_> = analysis.unresolved().map(|c| c.id.as_str()).collect();
+        assert_eq!(unresolved, vec!["2"]);
+        let unused: Vec<_> = analysis.unused().map(|s| s.label.as_str()).collect();
+        assert_eq!(unused, vec!["3"]);
+
+        let source = &analysis.sources[0];
+        assert_eq!(source.kind, SourceKind::LinkDefinition);
+        assert_eq!(source.title.as_deref(), Some("A"));
+        assert_eq!(source.raw, "[1]: https://a.com \"A\"");
+        assert_eq!(source.line, 3);
+        assert_eq!(source.citations, vec![0]);
+        assert_eq!(source.domain().as_deref(), Some("a.com"));
+        assert_eq!(source.kind.to_string(), "link-definition");
+    }
+}
@@ -0,0 +1,207 @@
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::names::named_enum;
+
+/// Vendor-specific leftovers removed besides citation markers
+///
+/// None are removed by default; [`Provider`](crate::Provider) profiles turn on
+/// the ones their vendor produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Artifact {
+    /// ChatGPT's `:contentReference[oaicite:0]{index=0}` copy artifacts
+    ContentReference,
+    /// ChatGPT's `citeturn0search0` tokens, with or without their
+    /// private-use delimiters
+    CiteTurn,
+    /// `utm_*` tracking parameters in URLs, e.g. `?utm_source=chatgpt.com`
+    TrackingParams,
+    /// Gemini's `[cite_start]` markers and multi-source `[cite: 1, 2]`
+    CiteStart,
+    /// Copilot's `[^1^]` markers
+    CopilotFootnotes,
+    /// Copilot's `Source: Conversation with Copilot` list of `(1) …` entries
+    CopilotSources,
+    /// The logo and `⁂` separators of Perplexity exports, with the blank
+    /// lines after them
+    PerplexityChrome,
+}
+
+named_enum! {
+    Artifact, "artifact" {
+        ContentReference => "content-reference",
+        CiteTurn => "cite-turn",
+        TrackingParams => "tracking-params",
+        CiteStart => "cite-start",
+        CopilotFootnotes => "copilot-footnotes",
+        CopilotSources => "copilot-sources",
+        PerplexityChrome => "perplexity-chrome",
+    }
+}
+
+impl Artifact {
+    /// Whether the cleanup removes inline citation markers
+    pub(crate) fn is_inline(self) -> bool {
+        matches!(
+            self,
+            Self::ContentReference | Self::CiteTurn | Self::CiteStart | Self::CopilotFootnotes
+        )
+    }
+
+    /// Whether the cleanup removes a reference list
+    pub(crate) fn is_reference(self) -> bool {
+        matches!(self, Self::CopilotSources)
+    }
+
+    /// Ranges of `text` to delete, with the spaces before inline tokens
+    pub(crate) fn ranges(self, text: &str) -> Vec<Range<usize>> {
+        let regex = match self {
+            Self::TrackingParams => return tracking_params(text),
+            Self::ContentReference => &CONTENT_REFERENCE,
+            Self::CiteTurn => &CITE_TURN,
+            Self::CiteStart => &CITE_START,
+            Self::CopilotFootnotes => &COPILOT_FOOTNOTES,
+            Self::CopilotSources => &COPILOT_SOURCES,
+            Self::PerplexityChrome => &PERPLEXITY_CHROME,
+        };
+        regex.find_iter(text).map(|m| m.range()).collect()
+    }
+}
+
+static CONTENT_REFERENCE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[ \t]*:?contentReference\[oaicite:\d+\](?:\{index=\d+\})?").unwrap());
+
+/// `\u{E200}cite\u{E202}turn0search0\u{E201}` as exported, or `citeturn0search0`
+/// once the private-use characters are dropped
+static CITE_TURN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[ \t]*(?:\x{E200}cite(?:\x{E202}turn\d+[a-z]+\d+)+\x{E201}|\bcite(?:turn\d+[a-z]+\d+)+\b)")
+        .unwrap()
+});
+
+static CITE_START: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[cite_start\]|\[cite:\s*\d+(?:\s*,\s*\d+)+\]").unwrap());
+
+static COPILOT_FOOTNOTES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\^\d+\^\]").unwrap());
+
+static COPILOT_SOURCES: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^Source: Conversation with (?:Copilot|Bing)[^\n]*(?:\n\(\d+\)[^\n]*)*\n?")
+        .unwrap()
+});
+
+static PERPLEXITY_CHROME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?m)^[ \t]*(?:<img\s[^>\n]*src="https://r2cdn\.perplexity\.ai/[^"\n]*"[^>\n]*>|<div[^>\n]*>\s*⁂\s*</div>)[ \t]*(?:\n|$)(?:[ \t]*\n)*"#,
+    )
+    .unwrap()
+});
+
+/// `utm_*` parameters in URLs, with the separator that would be left dangling
+fn tracking_params(text: &str) -> Vec<Range<usize>> {
+    static URL: Lazy<Regex> = Lazy::new(|| Regex::new(r#"https?://[^\s<>()\[\]"']+"#).unwrap());
+    static PARAMS: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"[?&]utm_\w+=[^&#]*(?:&utm_\w+=[^&#]*)*").unwrap());
+
+    let mut ranges = Vec::new();
+    for url in URL.find_iter(text) {
+        for params in PARAMS.find_iter(url.as_str()) {
+            let start = url.start() + params.start();
+            let end = url.start() + params.end();
+            // `?utm_source=x&id=2` keeps `?id=2`; `?id=2&utm_source=x` keeps `?id=2`
+            if text[end..].starts_with('&') {
+                ranges.push(start + 1..end + 1);
+            } else {
+                ranges.push(start..end);
+            }
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(artifact: Artifact, text: &str) -> String {
+        let mut text = text.to_string();
+        for range in artifact.ranges(&text.clone()).into_iter().rev() {
+            text.replace_range(range, "");
+        }
+        text
+    }
+
+    #[test]
+    fn test_chatgpt_artifacts() {
+        assert_eq!(
+            strip(
+                Artifact::ContentReference,
+                "Fact :contentReference[oaicite:0]{index=0}."
+            ),
+            "Fact."
+        );
+        assert_eq!(
+            strip(
+                Artifact::CiteTurn,
+                "Fact \u{E200}cite\u{E202}turn0search0\u{E202}turn1news3\u{E201}. More citeturn0search2."
+            ),
+            "Fact. More."
+        );
+        assert_eq!(
+            strip(Artifact::CiteTurn, "excite turnips"),
+            "excite turnips"
+        );
+    }
+
+    #[test]
+    fn test_tracking_params() {
+        let strip = |text| strip(Artifact::TrackingParams, text);
+        assert_eq!(
+            strip("[a](https://a.com/x?utm_source=chatgpt.com)"),
+            "[a](https://a.com/x)"
+        );
+        assert_eq!(
+            strip("https://a.com/?utm_source=x&utm_medium=y&id=2#top"),
+            "https://a.com/?id=2#top"
+        );
+        assert_eq!(
+            strip("https://a.com/?id=2&utm_source=x"),
+            "https://a.com/?id=2"
+        );
+        assert_eq!(
+            strip("Set utm_source=x in the form"),
+            "Set utm_source=x in the form"
+        );
+    }
+
+    #[test]
+    fn test_gemini_copilot_and_perplexity_artifacts() {
+        assert_eq!(
+            strip(Artifact::CiteStart, "[cite_start]Fact one.[cite: 1, 2]"),
+            "Fact one."
+        );
+        assert_eq!(strip(Artifact::CopilotFootnotes, "Fact[^1^]."), "Fact.");
+        assert_eq!(
+            strip(
+                Artifact::CopilotSources,
+                "Fact.\nSource: Conversation with Copilot, 6/3/2024\n(1) Title. https://a.com.\n(2) Other. https://b.com.\n"
+            ),
+            "Fact.\n"
+        );
+        assert_eq!(
+            strip(
+                Artifact::PerplexityChrome,
+                "<img src=\"https://r2cdn.perplexity.ai/pplx-full-logo-primary-dark%402x.png\" class=\"logo\" width=\"120\"/>\n\n# Answer\n\n<div style=\"text-align: center\">⁂</div>\n"
+            ),
+            "# Answer\n\n"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use markdown_ai_cite_remove::{CitationRemover, RemovalCategory, RemoverConfig, RuleId};
+use serde_json::json;
+
+use crate::{apply_removal, inputs, resolve_config, CheckArgs, EXIT_ERROR};
+
+/// Exit code when some input still contains citations
+const EXIT_FOUND: u8 = 1;
+
+/// Longest snippet shown, in characters
+const SNIPPET_LENGTH: usize = 60;
+
+/// How `mdcr check` reports what it found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// `file:line:col: rule: snippet`
+    Human,
+    /// GitHub Actions workflow commands, shown as annotations on the diff
+    Github,
+    /// SARIF 2.1.0, for code scanning tools
+    Sarif,
+}
+
+/// Something the remover would take out of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub rule: RuleId,
+    /// First line of the text that would be removed, shortened
+    pub snippet: String,
+}
+
+impl Finding {
+    fn message(&self) -> String {
+        format!("{} `{}`", describe(&self.rule), self.snippet)
+    }
+}
+
+/// What the remover would take out of `input`, in input order
+///
+/// Whitespace fixes are left out: they only tidy up after other removals, or
+/// fix formatting that has nothing to do with citations.
+pub fn findings(path: &str, input: &str, config: RemoverConfig) -> Vec<Finding> {
+    let (_, report) = CitationRemover::with_config(config).remove_with_report(input);
+    let mut removals: Vec<_> = report
+        .removals
+        .into_iter()
+        .filter(|r| r.rule.category() != RemovalCategory::Whitespace)
+        .collect();
+    removals.sort_by_key(|r| r.range.start);
+    removals
+        .into_iter()
+        .map(|removal| Finding {
+            path: path.to_string(),
+            line: removal.line,
+            column: removal.column,
+            rule: removal.rule.clone(),
+            snippet: snippet(&removal.text),
+        })
+        .collect()
+}
+
+fn snippet(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    if line.chars().count() > SNIPPET_LENGTH {
+        let short: String = line.chars().take(SNIPPET_LENGTH - 1).collect();
+        format!("{}…", short)
+    } else {
+        line.to_string()
+    }
+}
+
+/// What a rule finds, for messages and SARIF rule descriptions
+fn describe(rule: &RuleId) -> &'static str {
+    match rule.category() {
+        RemovalCategory::Inline => "Inline citation",
+        RemovalCategory::Definition => "Citation definition",
+        RemovalCategory::Header => "Reference section",
+        RemovalCategory::Entry => "Bibliography entry",
+        RemovalCategory::Artifact => "AI chat artifact",
+        RemovalCategory::Whitespace => "Whitespace",
+        RemovalCategory::Custom => "Custom rule match",
+    }
+}
+
+/// Check the inputs of `mdcr check`, or stdin, and print the findings
+pub fn run(args: &CheckArgs, config: &RemoverConfig) -> ExitCode {
+    let settings = &args.settings;
+    let (checked, ok) = inputs::map_texts(&args.inputs, args.recursive, |name, input, log| {
+        if settings.verbose {
+            log.push(format!("Checking: {}", name));
+        }
+        let config = apply_removal(&args.removal, resolve_config(settings, config, input, log));
+        findings(name, input, config)
+    });
+    let files = checked.len();
+    let found: Vec<_> = checked.into_iter().flatten().collect();
+
+    print!(
+        "{}",
+        match args.format {
+            Format::Human => human(&found),
+            Format::Github => github(&found),
+            Format::Sarif => sarif(&found),
+        }
+    );
+    let mut paths: Vec<_> = found.iter().map(|f| &f.path).collect();
+    paths.dedup();
+    if !found.is_empty() {
+        eprintln!(
+            "Found {} citation(s) in {} of {} file(s)",
+            found.len(),
+            paths.len(),
+            files
+        );
+    } else if files > 0 {
+        eprintln!("No citations found in {} file(s)", files);
+    }
+
+    if !ok {
+        ExitCode::from(EXIT_ERROR)
+    } else if !found.is_empty() {
+        ExitCode::from(EXIT_FOUND)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// One `file:line:col: rule: message` line per finding
+pub fn human(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| {
+            format!(
+                "{}:{}:{}: {}: {}\n",
+                f.path,
+                f.line,
+                f.column,
+                f.rule,
+                f.message()
+            )
+        })
+        .collect()
+}
+
+/// One `::error` workflow command per finding
+pub fn github(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| {
+            format!(
+                "::error file={},line={},col={},title={}::{}\n",
+                escape_property(&f.path),
+                f.line,
+                f.column,
+                escape_property(&format!("mdcr {}", f.rule)),
+                escape_data(&f.message())
+            )
+        })
+        .collect()
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// A SARIF 2.1.0 log with one result per finding
+pub fn sarif(findings: &[Finding]) -> String {
+    let mut rules: Vec<RuleId> = Vec::new();
+    for finding in findings {
+        if !rules.contains(&finding.rule) {
+            rules.push(finding.rule.clone());
+        }
+    }
+    let results: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "ruleId": f.rule.as_str(),
+                "ruleIndex": rules.iter().position(|rule| *rule == f.rule),
+                "level": "error",
+                "message": { "text": f.message() },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": f.path.replace('\\', "/") },
+                        "region": {
+                            "startLine": f.line,
+                            "startColumn": f.column,
+                            "snippet": { "text": f.snippet },
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+    let rules: Vec<_> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.as_str(),
+                "shortDescription": { "text": describe(rule) },
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mdcr",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+    format!("{:#}\n", log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(input: &str) -> Vec<Finding> {
+        findings("doc.md", input, RemoverConfig::default())
+    }
+
+    #[test]
+    fn test_findings_skip_whitespace_fixes() {
+        assert!(found("Clean  text.   \n\n\n\nMore.\n").is_empty());
+
+        let findings = found("Fact[1] and more[2].\n\n[1]: https://example.com\n");
+        let places: Vec<_> = findings
+            .iter()
+            .map(|f| (f.line, f.column, f.rule.as_str(), f.snippet.as_str()))
+            .collect();
+        assert_eq!(
+            places,
+            [
+                (1, 5, "inline-numeric", "[1]"),
+                (1, 17, "inline-numeric", "[2]"),
+                (3, 1, "link-definition", "[1]: https://example.com"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_human_and_github_output() {
+        let findings = found("Fact[1].");
+        assert_eq!(
+            human(&findings),
+            "doc.md:1:5: inline-numeric: Inline citation `[1]`\n"
+        );
+
+        let findings = vec![Finding {
+            path: "a,b.md".into(),
+            snippet: "100%\nsure".into(),
+            ..findings[0].clone()
+        }];
+        assert_eq!(
+            github(&findings),
+            "::error file=a%2Cb.md,line=1,col=5,title=mdcr inline-numeric::Inline citation `100%25%0Asure`\n"
+        );
+    }
+
+    #[test]
+    fn test_sarif_output() {
+        let log: serde_json::Value = serde_json::from_str(&sarif(&found("Fact[1].[^2]"))).unwrap();
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "inline-footnote");
+
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], "inline-footnote");
+        assert_eq!(result["ruleIndex"], 1);
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 9);
+        assert_eq!(region["snippet"]["text"], "[^2]");
+    }
+
+    #[test]
+    fn test_long_snippets_are_shortened() {
+        let long = format!("[1] {}\nsecond line", "x".repeat(100));
+        let short = snippet(&long);
+        assert_eq!(short.chars().count(), SNIPPET_LENGTH);
+        assert!(short.ends_with('…'));
+        assert_eq!(snippet("  [1]: url\n"), "[1]: url");
+    }
+}
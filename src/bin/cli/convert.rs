@@ -0,0 +1,166 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use markdown_ai_cite_remove::{CitationFormat, CitationRemover, RemoverConfig};
+
+use crate::write::{self, InPlace};
+use crate::{inputs, resolve_config, ConvertArgs, EXIT_ERROR};
+
+/// Citation style `mdcr convert` converts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Style {
+    /// `[^1]` markers and `[^1]: [Title](url)` definitions
+    Footnotes,
+    /// Markdown links in place of the markers
+    InlineLinks,
+}
+
+/// The library format for `--to` and `--link-style`
+fn format(args: &ConvertArgs) -> Result<CitationFormat, String> {
+    match (args.to, args.link_style) {
+        (Style::Footnotes, None) => Ok(CitationFormat::Footnotes),
+        (Style::Footnotes, Some(_)) => Err("--link-style needs --to inline-links".into()),
+        (Style::InlineLinks, style) => Ok(CitationFormat::InlineLinks(style.unwrap_or_default())),
+    }
+}
+
+/// Convert the inputs of `mdcr convert`, or stdin
+pub fn run(args: &ConvertArgs, config: &RemoverConfig) -> ExitCode {
+    let format = match format(args) {
+        Ok(format) => format,
+        Err(message) => {
+            eprintln!("mdcr: {}", message);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    if let Err(e) = check_output(args) {
+        eprintln!("mdcr: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    let convert = |input: &str, log: &mut Vec<String>| {
+        let config = resolve_config(&args.settings, config, input, log);
+        CitationRemover::with_config(config).convert(input, format)
+    };
+
+    if args.inputs.is_empty() {
+        let mut input = String::new();
+        let mut log = Vec::new();
+        let result = io::stdin()
+            .read_to_string(&mut input)
+            .and_then(|_| output(args, &convert(&input, &mut log)));
+        for line in &log {
+            eprintln!("{}", line);
+        }
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("mdcr: {}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        };
+    }
+
+    let (files, errors) = inputs::expand(&args.inputs, args.recursive);
+    for error in &errors {
+        eprintln!("mdcr: {}: {}", error.arg, error.message);
+    }
+    if files.len() > 1 && !args.in_place {
+        eprintln!(
+            "mdcr: convert needs a single input file without --in-place, got {}",
+            files.len()
+        );
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    let in_place = InPlace {
+        backup_suffix: args.backup_suffix.clone(),
+        follow_symlinks: args.follow_symlinks,
+        ..Default::default()
+    };
+    let mut failed = false;
+    for file in &files {
+        let mut log = Vec::new();
+        let checked = if args.in_place {
+            in_place.check(&file.path)
+        } else {
+            Ok(())
+        };
+        let result = checked
+            .and_then(|()| fs::read_to_string(&file.path))
+            .and_then(|input| {
+                let converted = convert(&input, &mut log);
+                if !args.in_place {
+                    output(args, &converted)
+                } else if converted != input {
+                    if args.settings.verbose {
+                        log.push(format!("Converted {}", file.path.display()));
+                    }
+                    in_place.replace(&file.path, &converted)
+                } else {
+                    Ok(())
+                }
+            });
+        for line in &log {
+            eprintln!("{}", line);
+        }
+        if let Err(e) = result {
+            failed = true;
+            eprintln!("mdcr: {}: {}", file.path.display(), e);
+        }
+    }
+
+    if failed || !errors.is_empty() || files.is_empty() {
+        ExitCode::from(EXIT_ERROR)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Fail early if `--output` couldn't be written, before reading any input
+fn check_output(args: &ConvertArgs) -> io::Result<()> {
+    match args.output.as_deref() {
+        None | Some("-") => Ok(()),
+        Some(path) => write::check_writable(path.as_ref())
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e))),
+    }
+}
+
+/// Write `text` to `--output`, or stdout
+fn output(args: &ConvertArgs, text: &str) -> io::Result<()> {
+    match args.output.as_deref() {
+        None | Some("-") => io::stdout().lock().write_all(text.as_bytes()),
+        Some(path) => write::write_atomic(path.as_ref(), text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command};
+    use clap::Parser;
+    use markdown_ai_cite_remove::LinkStyle;
+
+    fn format_of(args: &[&str]) -> Result<CitationFormat, String> {
+        let cli = Cli::try_parse_from(["mdcr", "convert"].iter().chain(args)).unwrap();
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected the convert command");
+        };
+        format(&args)
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_of(&[]), Ok(CitationFormat::Footnotes));
+        assert_eq!(
+            format_of(&["--to", "inline-links"]),
+            Ok(CitationFormat::InlineLinks(LinkStyle::default()))
+        );
+        assert_eq!(
+            format_of(&["--to", "Inline-Links", "--link-style", "linked-word"]),
+            Ok(CitationFormat::InlineLinks(LinkStyle::LinkedWord))
+        );
+        assert!(format_of(&["--link-style", "superscript"]).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+
+use clap::ColorChoice;
+use markdown_ai_cite_remove::{CitationRemover, RemoverConfig};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{apply_removal, inputs, resolve_config, CleanArgs, EXIT_ERROR};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Widest `+`/`-` bar printed by `--stat`
+const STAT_WIDTH: usize = 40;
+
+/// Lines added and removed in one file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stat {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl Stat {
+    fn of<'a>(diff: &TextDiff<'a, 'a, 'a, str>) -> Self {
+        let mut stat = Self::default();
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => stat.insertions += 1,
+                ChangeTag::Delete => stat.deletions += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+        stat
+    }
+
+    fn changed(&self) -> usize {
+        self.insertions + self.deletions
+    }
+}
+
+/// Print what cleaning the inputs would change, writing nothing
+pub fn run(args: &CleanArgs, config: &RemoverConfig) -> ExitCode {
+    let settings = &args.settings;
+    let (diffs, ok) = inputs::map_texts(&args.inputs, args.recursive, |name, input, log| {
+        let config = apply_removal(&args.removal, resolve_config(settings, config, input, log));
+        let output = CitationRemover::with_config(config).remove(input);
+        let diff = line_diff(input, &output);
+        let text = if args.stat {
+            String::new()
+        } else {
+            unified(&diff, name, args.context)
+        };
+        (name.to_string(), Stat::of(&diff), text)
+    });
+
+    let color = use_color(args.color);
+    let text = if args.stat {
+        let stats: Vec<_> = diffs
+            .into_iter()
+            .map(|(name, stat, _)| (name, stat))
+            .collect();
+        stat(&stats, color)
+    } else {
+        diffs
+            .iter()
+            .map(|(_, _, text)| if color { colorize(text) } else { text.clone() })
+            .collect()
+    };
+    if let Err(e) = io::stdout().lock().write_all(text.as_bytes()) {
+        eprintln!("mdcr: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_ERROR)
+    }
+}
+
+/// Line diff of `old` and `new`, marking lines without a final newline
+fn line_diff<'a>(old: &'a str, new: &'a str) -> TextDiff<'a, 'a, 'a, str> {
+    TextDiff::configure()
+        .newline_terminated(true)
+        .diff_lines(old, new)
+}
+
+/// Whether to colour output to stdout
+fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+    }
+}
+
+/// Unified diff of `diff` with `context` lines around each change, empty if
+/// nothing changed
+///
+/// Files are labelled `a/NAME` and `b/NAME`, so the diff applies with
+/// `git apply` or `patch -p1`.
+pub fn unified<'a>(diff: &'a TextDiff<'a, 'a, 'a, str>, name: &str, context: usize) -> String {
+    let (old, new) = if name == inputs::STDIN {
+        (name.to_string(), name.to_string())
+    } else {
+        let name = name.trim_start_matches('/');
+        (format!("a/{}", name), format!("b/{}", name))
+    };
+    if Stat::of(diff).changed() == 0 {
+        return String::new();
+    }
+    diff.unified_diff()
+        .context_radius(context)
+        .missing_newline_hint(true)
+        .header(&old, &new)
+        .to_string()
+}
+
+/// `text` with file headers in bold, hunk headers in cyan, and added and
+/// removed lines in green and red
+fn colorize(text: &str) -> String {
+    let mut colored = String::with_capacity(text.len());
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let style = match line.as_bytes().first() {
+            _ if i < 2 => BOLD,
+            Some(b'@') => CYAN,
+            Some(b'+') => GREEN,
+            Some(b'-') => RED,
+            _ => "",
+        };
+        if style.is_empty() {
+            colored.push_str(line);
+        } else {
+            let content = line.trim_end_matches('\n');
+            colored.push_str(style);
+            colored.push_str(content);
+            colored.push_str(RESET);
+            colored.push_str(&line[content.len()..]);
+        }
+    }
+    colored
+}
+
+/// One `NAME | COUNT +++--` line per changed file and a total, like
+/// `git diff --stat`
+pub fn stat(stats: &[(String, Stat)], color: bool) -> String {
+    let changed: Vec<_> = stats.iter().filter(|(_, s)| s.changed() > 0).collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    let name_width = changed
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or_default();
+    let most = changed
+        .iter()
+        .map(|(_, s)| s.changed())
+        .max()
+        .unwrap_or_default();
+    let count_width = most.to_string().len();
+    // Scale the bars down to STAT_WIDTH, keeping at least one of each sign
+    let scale = |n: usize| {
+        if most <= STAT_WIDTH || n == 0 {
+            n
+        } else {
+            (n * STAT_WIDTH / most).max(1)
+        }
+    };
+    let (plus, minus, reset) = if color {
+        (GREEN, RED, RESET)
+    } else {
+        ("", "", "")
+    };
+
+    let mut text = String::new();
+    for (name, stat) in &changed {
+        text.push_str(&format!(
+            " {:<name_width$} | {:>count_width$} {}{}{}{}{}{}\n",
+            name,
+            stat.changed(),
+            plus,
+            "+".repeat(scale(stat.insertions)),
+            reset,
+            minus,
+            "-".repeat(scale(stat.deletions)),
+            reset,
+        ));
+    }
+
+    let insertions: usize = changed.iter().map(|(_, s)| s.insertions).sum();
+    let deletions: usize = changed.iter().map(|(_, s)| s.deletions).sum();
+    let mut summary = format!(
+        " {} file{} changed",
+        changed.len(),
+        if changed.len() == 1 { "" } else { "s" }
+    );
+    if insertions > 0 {
+        summary.push_str(&format!(
+            ", {} insertion{}(+)",
+            insertions,
+            if insertions == 1 { "" } else { "s" }
+        ));
+    }
+    if deletions > 0 {
+        summary.push_str(&format!(
+            ", {} deletion{}(-)",
+            deletions,
+            if deletions == 1 { "" } else { "s" }
+        ));
+    }
+    text.push_str(&summary);
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "# Title\n\nFact[1].\n\nOne\nTwo\nThree\nFour\nFive\n\nMore[2].\n";
+
+    #[test]
+    fn test_unified_diff_with_context() {
+        let output = "# Title\n\nFact.\n\nOne\nTwo\nThree\nFour\nFive\n\nMore.\n";
+        let diff = line_diff(INPUT, output);
+        let text = unified(&diff, "doc.md", 1);
+        assert!(text.starts_with("--- a/doc.md\n+++ b/doc.md\n@@ -2,3 +2,3 @@\n"));
+        assert!(text.contains("\n-Fact[1].\n+Fact.\n"));
+        assert_eq!(text.matches("@@ -").count(), 2);
+
+        // With more context the hunks merge
+        assert_eq!(unified(&diff, "doc.md", 4).matches("@@ -").count(), 1);
+
+        let same = line_diff(INPUT, INPUT);
+        assert_eq!(unified(&same, "doc.md", 3), "");
+        let stdin = unified(&diff, inputs::STDIN, 3);
+        assert!(stdin.starts_with("--- <stdin>\n+++ <stdin>\n"));
+
+        let unterminated = line_diff("Fact[1].\n", "Fact.");
+        assert!(unified(&unterminated, "doc.md", 3)
+            .ends_with("-Fact[1].\n+Fact.\n\\ No newline at end of file\n"));
+    }
+
+    #[test]
+    fn test_clean_files_have_no_diff() {
+        let remover = CitationRemover::new();
+        let clean = "Clean text.\n";
+        let output = remover.remove(clean);
+        let diff = line_diff(clean, &output);
+        assert_eq!(Stat::of(&diff), Stat::default());
+        assert_eq!(unified(&diff, "c3.md", 3), "");
+
+        let output = remover.remove(INPUT);
+        let diff = line_diff(INPUT, &output);
+        assert_eq!(
+            Stat::of(&diff),
+            Stat {
+                insertions: 2,
+                deletions: 2
+            }
+        );
+        assert!(!unified(&diff, "doc.md", 3).contains("No newline"));
+    }
+
+    #[test]
+    fn test_colorize() {
+        let text = "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-Fact[1].\n+Fact.\n same\n";
+        assert_eq!(
+            colorize(text),
+            format!(
+                "{BOLD}--- a/x{RESET}\n{BOLD}+++ b/x{RESET}\n{CYAN}@@ -1 +1 @@{RESET}\n\
+                 {RED}-Fact[1].{RESET}\n{GREEN}+Fact.{RESET}\n same\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_stat() {
+        let stats = [
+            (
+                "docs/a.md".to_string(),
+                Stat {
+                    insertions: 2,
+                    deletions: 5,
+                },
+            ),
+            ("b.md".to_string(), Stat::default()),
+            (
+                "c.md".to_string(),
+                Stat {
+                    insertions: 0,
+                    deletions: 100,
+                },
+            ),
+        ];
+        let text = stat(&stats, false);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], " docs/a.md |   7 +--");
+        assert_eq!(
+            lines[1],
+            format!(" c.md      | 100 {}", "-".repeat(STAT_WIDTH))
+        );
+        assert_eq!(
+            lines[2],
+            " 2 files changed, 2 insertions(+), 105 deletions(-)"
+        );
+        assert_eq!(stat(&stats[1..2], false), "");
+    }
+}
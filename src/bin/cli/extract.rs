@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use markdown_ai_cite_remove::{
+    CitationAnalysis, CitationRemover, RemoverConfig, Source, SourcesFormat,
+};
+use serde_json::json;
+
+use crate::{inputs, resolve_config, ExtractArgs, EXIT_ERROR};
+
+/// What `mdcr extract` prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Citations and sources of every file
+    Json,
+    /// One row per source
+    Csv,
+    /// CSL-JSON bibliography of the sources of every file
+    CslJson,
+    /// BibTeX bibliography of the sources of every file
+    #[value(name = "bibtex")]
+    BibTex,
+    /// RIS bibliography of the sources of every file
+    Ris,
+}
+
+impl Format {
+    /// The bibliography format, if this is one
+    pub fn sources(self) -> Option<SourcesFormat> {
+        match self {
+            Self::Json | Self::Csv => None,
+            Self::CslJson => Some(SourcesFormat::CslJson),
+            Self::BibTex => Some(SourcesFormat::BibTex),
+            Self::Ris => Some(SourcesFormat::Ris),
+        }
+    }
+}
+
+/// Print the citations and sources of the inputs of `mdcr extract`, or stdin
+pub fn run(args: &ExtractArgs, config: &RemoverConfig) -> ExitCode {
+    let settings = &args.settings;
+    let (analyses, ok) = inputs::map_texts(&args.inputs, args.recursive, |name, input, log| {
+        let config = resolve_config(settings, config, input, log);
+        let analysis = CitationRemover::with_config(config).analyze(input);
+        if settings.verbose {
+            log.push(format!(
+                "{}: {} citation(s), {} source(s)",
+                name,
+                analysis.citations.len(),
+                analysis.sources.len()
+            ));
+        }
+        (name.to_string(), analysis)
+    });
+
+    let text = match args.format.sources() {
+        Some(format) => bibliography(&analyses, format),
+        None if args.format == Format::Csv => csv(&analyses),
+        None => json(&analyses),
+    };
+    if let Err(e) = io::stdout().lock().write_all(text.as_bytes()) {
+        eprintln!("mdcr: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_ERROR)
+    }
+}
+
+/// An array with the file name, citations and sources of every input
+pub fn json(analyses: &[(String, CitationAnalysis)]) -> String {
+    let files: Vec<_> = analyses
+        .iter()
+        .map(|(name, analysis)| {
+            json!({
+                "file": name,
+                "citations": analysis.citations,
+                "sources": analysis.sources,
+            })
+        })
+        .collect();
+    format!("{:#}\n", serde_json::Value::Array(files))
+}
+
+/// A header and one row per source
+pub fn csv(analyses: &[(String, CitationAnalysis)]) -> String {
+    let mut text = String::from("file,line,label,kind,url,domain,title,citations\n");
+    for (name, analysis) in analyses {
+        for source in &analysis.sources {
+            let row = [
+                name.clone(),
+                source.line.to_string(),
+                source.label.clone(),
+                source.kind.to_string(),
+                source.url.clone().unwrap_or_default(),
+                source.domain().unwrap_or_default(),
+                source.title.clone().unwrap_or_default(),
+                source.citations.len().to_string(),
+            ];
+            let row: Vec<_> = row.iter().map(|field| csv_field(field)).collect();
+            text.push_str(&row.join(","));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// `field`, quoted if it contains a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// One bibliography for all inputs
+///
+/// Sources with a URL already seen in an earlier file are left out. With
+/// several files, sources are numbered through so their keys stay unique.
+pub fn bibliography(analyses: &[(String, CitationAnalysis)], format: SourcesFormat) -> String {
+    if let [(_, analysis)] = analyses {
+        return analysis.export_sources(format);
+    }
+    let mut seen = HashSet::new();
+    let sources: Vec<Source> = analyses
+        .iter()
+        .flat_map(|(_, analysis)| &analysis.sources)
+        .filter(|source| source.url.as_ref().map_or(true, |url| seen.insert(url)))
+        .enumerate()
+        .map(|(i, source)| Source {
+            label: (i + 1).to_string(),
+            citations: Vec::new(),
+            ..source.clone()
+        })
+        .collect();
+    CitationAnalysis {
+        citations: Vec::new(),
+        sources,
+    }
+    .export_sources(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use markdown_ai_cite_remove::extract_citations;
+
+    fn analyses() -> Vec<(String, CitationAnalysis)> {
+        vec![
+            (
+                "a.md".to_string(),
+                extract_citations(
+                    "Fact[1][2].\n\n[1]: https://www.Example.com/a \"Title, with comma\"\n[2]: https://b.org",
+                ),
+            ),
+            (
+                "b.md".to_string(),
+                extract_citations("More[1].\n\n[1]: https://b.org"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_json() {
+        let value: serde_json::Value = serde_json::from_str(&json(&analyses())).unwrap();
+        assert_eq!(value[0]["file"], "a.md");
+        assert_eq!(value[0]["citations"][1]["id"], "2");
+        assert_eq!(value[1]["sources"][0]["url"], "https://b.org");
+    }
+
+    #[test]
+    fn test_csv() {
+        let text = csv(&analyses());
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            "a.md,3,1,link-definition,https://www.Example.com/a,example.com,\"Title, with comma\",1"
+        );
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_bibliography_merges_files() {
+        let bibtex = bibliography(&analyses(), SourcesFormat::BibTex);
+        assert_eq!(bibtex.matches("@online{").count(), 2);
+        assert!(bibtex.contains("@online{ref1,") && bibtex.contains("@online{ref2,"));
+
+        let single = bibliography(&analyses()[1..], SourcesFormat::Ris);
+        assert_eq!(single.matches("TY  - ELEC").count(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+
+/// Per-directory ignore file, with the same syntax as `.gitignore`
+pub const IGNORE_FILE: &str = ".mdcrignore";
+
+/// Name of stdin in messages
+pub const STDIN: &str = "<stdin>";
+
+/// Extensions of the files collected from directories
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdx"];
+
+/// A file to process
+pub struct InputFile {
+    pub path: PathBuf,
+    /// The directory argument the file was found under
+    base: Option<PathBuf>,
+}
+
+impl InputFile {
+    /// Whether the file was found by walking a directory rather than named
+    pub fn found_in_directory(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// Where the file goes below `--output-dir`
+    ///
+    /// Relative paths inside the current directory are kept as they are;
+    /// other files keep their path below the directory argument they were
+    /// found under, or just their name.
+    pub fn mirrored(&self) -> PathBuf {
+        if let Some(path) = inside(&self.path) {
+            return path;
+        }
+        if let Some(base) = &self.base {
+            if let Ok(relative) = self.path.strip_prefix(base) {
+                return match base.file_name() {
+                    Some(name) => Path::new(name).join(relative),
+                    None => relative.to_path_buf(),
+                };
+            }
+        }
+        self.path.file_name().map(PathBuf::from).unwrap_or_default()
+    }
+}
+
+/// `path` without `.` components, if it is relative and does not leave the
+/// current directory
+fn inside(path: &Path) -> Option<PathBuf> {
+    let mut inner = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => inner.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(inner)
+}
+
+/// An argument that did not name any file
+pub struct InputError {
+    pub arg: String,
+    pub message: String,
+}
+
+/// Files named by the `INPUT` arguments, in argument order and without
+/// duplicates
+///
+/// Arguments that don't exist are expanded as globs. Directories are walked
+/// with `recursive`, skipping what `.gitignore` and `.mdcrignore` files
+/// exclude; explicitly named files are always included.
+pub fn expand(args: &[String], recursive: bool) -> (Vec<InputFile>, Vec<InputError>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |file: InputFile, files: &mut Vec<InputFile>| {
+        if seen.insert(file.path.clone()) {
+            files.push(file);
+        }
+    };
+
+    for arg in args {
+        let error = |message: &str| InputError {
+            arg: arg.clone(),
+            message: message.to_string(),
+        };
+        let path = Path::new(arg);
+        if path.is_dir() {
+            if !recursive {
+                errors.push(error("is a directory (use --recursive)"));
+                continue;
+            }
+            for file in walk(path) {
+                add(file, &mut files);
+            }
+        } else if path.exists() || !is_glob(arg) {
+            add(
+                InputFile {
+                    path: path.to_path_buf(),
+                    base: None,
+                },
+                &mut files,
+            );
+        } else {
+            let paths = match glob::glob(arg) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    errors.push(error(&format!("invalid pattern: {}", e)));
+                    continue;
+                }
+            };
+            let mut matched = false;
+            for path in paths.flatten() {
+                matched = true;
+                if path.is_dir() {
+                    if recursive {
+                        for file in walk(&path) {
+                            add(file, &mut files);
+                        }
+                    }
+                } else {
+                    add(InputFile { path, base: None }, &mut files);
+                }
+            }
+            if !matched {
+                errors.push(error("no files match"));
+            }
+        }
+    }
+    (files, errors)
+}
+
+/// Apply `f` to the text of every input named by `args`, or of stdin if there
+/// are none, in parallel
+///
+/// `f` gets the name of the input, its text, and a log that is printed to
+/// stderr. Inputs that can't be found or read are reported and skipped.
+/// Returns the results in input order, and whether every input was read.
+pub fn map_texts<T, F>(args: &[String], recursive: bool, f: F) -> (Vec<T>, bool)
+where
+    T: Send,
+    F: Fn(&str, &str, &mut Vec<String>) -> T + Sync,
+{
+    if args.is_empty() {
+        let mut text = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut text) {
+            eprintln!("mdcr: {}", e);
+            return (Vec::new(), false);
+        }
+        let mut log = Vec::new();
+        let result = f(STDIN, &text, &mut log);
+        for line in &log {
+            eprintln!("{}", line);
+        }
+        return (vec![result], true);
+    }
+
+    let (files, errors) = expand(args, recursive);
+    for error in &errors {
+        eprintln!("mdcr: {}: {}", error.arg, error.message);
+    }
+    let mut ok = errors.is_empty() && !files.is_empty();
+
+    let outcomes: Vec<_> = files
+        .par_iter()
+        .map(|file| {
+            let name = file.path.display().to_string();
+            let mut log = Vec::new();
+            let result = fs::read_to_string(&file.path).map(|text| f(&name, &text, &mut log));
+            (name, log, result)
+        })
+        .collect();
+    let mut results = Vec::new();
+    for (name, log, result) in outcomes {
+        for line in &log {
+            eprintln!("{}", line);
+        }
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                ok = false;
+                eprintln!("mdcr: {}: {}", name, e);
+            }
+        }
+    }
+    (results, ok)
+}
+
+fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Markdown files below `dir`, sorted by path, except earlier output
+fn walk(dir: &Path) -> Vec<InputFile> {
+    WalkBuilder::new(dir)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_markdown(path) && !is_output(path))
+        .map(|path| InputFile {
+            path,
+            base: Some(dir.to_path_buf()),
+        })
+        .collect()
+}
+
+/// Whether `path` was written next to its input by an earlier run
+fn is_output(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with(crate::OUTPUT_SUFFIX))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            MARKDOWN_EXTENSIONS
+                .iter()
+                .any(|md| ext.eq_ignore_ascii_case(md))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn file(path: &str, base: Option<&str>) -> InputFile {
+        InputFile {
+            path: PathBuf::from(path),
+            base: base.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn test_mirrored_paths() {
+        assert_eq!(file("./docs/a.md", None).mirrored(), Path::new("docs/a.md"));
+        assert_eq!(
+            file("/srv/docs/guide/a.md", Some("/srv/docs")).mirrored(),
+            Path::new("docs/guide/a.md")
+        );
+        assert_eq!(file("../a.md", None).mirrored(), Path::new("a.md"));
+    }
+
+    #[test]
+    fn test_expand_walks_globs_and_respects_ignore_files() {
+        let root = std::env::temp_dir().join(format!("mdcr-inputs-{}", std::process::id()));
+        fs::create_dir_all(root.join("drafts")).unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        for name in [
+            "a.md",
+            "a__cite_removed.md",
+            "b.markdown",
+            "notes.txt",
+            "drafts/c.md",
+            "build/d.md",
+        ] {
+            fs::write(root.join(name), "Fact[1].").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::write(root.join(IGNORE_FILE), "drafts/\n").unwrap();
+        let names = |files: &[InputFile]| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.path.strip_prefix(&root).unwrap().display().to_string())
+                .collect()
+        };
+
+        let dir = root.display().to_string();
+        let (files, errors) = expand(std::slice::from_ref(&dir), true);
+        assert_eq!(names(&files), ["a.md", "b.markdown"]);
+        assert!(errors.is_empty());
+
+        let (files, errors) = expand(&[dir], false);
+        assert!(files.is_empty());
+        assert_eq!(errors[0].message, "is a directory (use --recursive)");
+
+        // Named and globbed files are never skipped, and appear once
+        let drafts = root.join("drafts/c.md").display().to_string();
+        let pattern = root.join("*.md").display().to_string();
+        let (files, _) = expand(&[drafts.clone(), pattern, drafts], false);
+        assert_eq!(names(&files), ["drafts/c.md", "a.md", "a__cite_removed.md"]);
+
+        let missing = root.join("*.rst").display().to_string();
+        let (files, errors) = expand(&[missing], false);
+        assert!(files.is_empty());
+        assert_eq!(errors[0].message, "no files match");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,1285 @@
+mod check;
+mod convert;
+mod diff;
+mod extract;
+mod inputs;
+mod review;
+mod stats;
+mod write;
+
+use clap::{Args, ColorChoice, Parser, Subcommand};
+use inputs::InputFile;
+use markdown_ai_cite_remove::{
+    Artifact, CitationRemover, ConfigFile, Language, LinkStyle, Provider, RemovalCategory,
+    RemovalMode, RemovalReport, RemoverConfig, RemoverError, Replacement, SourcesFormat,
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use write::InPlace;
+
+#[derive(Parser)]
+#[command(name = "mdcr")]
+#[command(version, about = "Remove citations from AI-generated markdown", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Without a command, the arguments of `clean`
+    #[command(flatten)]
+    clean: CleanArgs,
+}
+
+#[derive(Args)]
+struct CleanArgs {
+    /// Input files, directories (with --recursive) or glob patterns (stdin if
+    /// none are given)
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Output file, or - for stdout (default: INPUT__cite_removed.md next to
+    /// the input, or stdout for stdin)
+    #[arg(short, long, conflicts_with = "output_dir")]
+    output: Option<String>,
+
+    /// Write the cleaned files below DIR, mirroring the input paths
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Process the Markdown files in directories, skipping those excluded by
+    /// .gitignore or .mdcrignore
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Number of files to process in parallel (default: one per CPU)
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Also write the extracted sources to a bibliography file
+    #[arg(long, value_name = "FILE")]
+    emit_sources: Option<String>,
+
+    /// Bibliography format for --emit-sources: csl-json, bibtex or ris
+    #[arg(long, value_name = "FORMAT", default_value_t = SourcesFormat::CslJson, requires = "emit_sources")]
+    sources_format: SourcesFormat,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(flatten)]
+    removal: Removal,
+
+    /// Overwrite each input with its cleaned version
+    #[arg(
+        short = 'i',
+        long,
+        conflicts_with_all = ["output", "output_dir"],
+        requires = "inputs",
+        help_heading = "In-place editing"
+    )]
+    in_place: bool,
+
+    /// Keep the original of each edited file as FILE<SUFFIX>, e.g. .bak
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        requires = "in_place",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help_heading = "In-place editing"
+    )]
+    backup_suffix: Option<String>,
+
+    /// Keep the permissions and modification time of edited files
+    #[arg(long, requires = "in_place", help_heading = "In-place editing")]
+    preserve: bool,
+
+    /// Edit the files that symbolic links point to, instead of refusing them
+    #[arg(long, requires = "in_place", help_heading = "In-place editing")]
+    follow_symlinks: bool,
+
+    /// Print a unified diff of what would be removed, instead of writing
+    /// anything
+    #[arg(
+        long,
+        conflicts_with_all = ["output", "output_dir", "in_place", "emit_sources"],
+        help_heading = "Preview"
+    )]
+    diff: bool,
+
+    /// Lines of context around each change in the diff
+    #[arg(
+        short = 'U',
+        long,
+        value_name = "N",
+        default_value_t = 3,
+        requires = "diff",
+        help_heading = "Preview"
+    )]
+    context: usize,
+
+    /// Print the number of changed lines per file instead of the diff
+    #[arg(long, requires = "diff", help_heading = "Preview")]
+    stat: bool,
+
+    /// When to colour the diff: auto, always or never
+    #[arg(
+        long,
+        value_name = "WHEN",
+        default_value = "auto",
+        requires = "diff",
+        help_heading = "Preview"
+    )]
+    color: ColorChoice,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Remove citations (the default when no command is given)
+    Clean(CleanArgs),
+
+    /// Print the citations and sources in files as JSON, CSV or a
+    /// bibliography
+    Extract(ExtractArgs),
+
+    /// Report the citations left in files without changing them
+    ///
+    /// Exits with status 1 if any input still contains citations, and 2 if
+    /// an input could not be read.
+    Check(CheckArgs),
+
+    /// Rewrite citations as footnotes or inline links
+    Convert(ConvertArgs),
+
+    /// Count citations, sources and cited domains across files
+    Stats(StatsArgs),
+
+    /// Step through the removals in a file, accepting or rejecting each, and
+    /// write the result
+    Review(ReviewArgs),
+}
+
+impl Command {
+    fn settings(&self) -> &Settings {
+        match self {
+            Self::Clean(args) => &args.settings,
+            Self::Extract(args) => &args.settings,
+            Self::Check(args) => &args.settings,
+            Self::Convert(args) => &args.settings,
+            Self::Stats(args) => &args.settings,
+            Self::Review(args) => &args.settings,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// Input files, directories (with --recursive) or glob patterns (stdin if
+    /// none are given)
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Read the Markdown files in directories, skipping those excluded by
+    /// .gitignore or .mdcrignore
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Output format
+    #[arg(long, value_name = "FORMAT", value_enum, ignore_case = true, default_value_t = extract::Format::Json)]
+    format: extract::Format,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    /// Input files, directories (with --recursive) or glob patterns (stdin if
+    /// none are given)
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Check the Markdown files in directories, skipping those excluded by
+    /// .gitignore or .mdcrignore
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// How to report citations
+    #[arg(long, value_name = "FORMAT", value_enum, ignore_case = true, default_value_t = check::Format::Human)]
+    format: check::Format,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(flatten)]
+    removal: Removal,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// Input files, directories (with --recursive) or glob patterns (stdin if
+    /// none are given)
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Convert the Markdown files in directories, skipping those excluded by
+    /// .gitignore or .mdcrignore
+    #[arg(short, long, requires = "in_place")]
+    recursive: bool,
+
+    /// Citation style to convert to
+    #[arg(long, value_name = "STYLE", value_enum, ignore_case = true, default_value_t = convert::Style::Footnotes)]
+    to: convert::Style,
+
+    /// How inline links are written: parenthesized, linked-word or superscript
+    #[arg(long, value_name = "STYLE")]
+    link_style: Option<LinkStyle>,
+
+    /// Output file, or - for stdout (default: stdout)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Overwrite each input with its converted version
+    #[arg(short = 'i', long, conflicts_with = "output", requires = "inputs")]
+    in_place: bool,
+
+    /// Keep the original of each edited file as FILE<SUFFIX>, e.g. .bak
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        requires = "in_place",
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    backup_suffix: Option<String>,
+
+    /// Edit the files that symbolic links point to, instead of refusing them
+    #[arg(long, requires = "in_place")]
+    follow_symlinks: bool,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+#[derive(Args)]
+struct StatsArgs {
+    /// Input files, directories (with --recursive) or glob patterns (stdin if
+    /// none are given)
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+
+    /// Read the Markdown files in directories, skipping those excluded by
+    /// .gitignore or .mdcrignore
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Number of domains to list (0 for all)
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+
+    /// Output format
+    #[arg(long, value_name = "FORMAT", value_enum, ignore_case = true, default_value_t = stats::Format::Human)]
+    format: stats::Format,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+#[derive(Args)]
+struct ReviewArgs {
+    /// File to review
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// Write the result to FILE instead of overwriting the input
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Keep the original as FILE<SUFFIX> when overwriting it, e.g. .bak
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        conflicts_with = "output",
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    backup_suffix: Option<String>,
+
+    /// Edit the file a symbolic link points to, instead of refusing it
+    #[arg(long, conflicts_with = "output")]
+    follow_symlinks: bool,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(flatten)]
+    removal: Removal,
+}
+
+/// Where the configuration comes from and how citations are recognised,
+/// shared by every command
+#[derive(Args)]
+struct Settings {
+    /// Where the input came from: auto, chatgpt, chatgpt-deep-research, perplexity,
+    /// perplexity-export, claude, gemini, copilot or deepseek
+    #[arg(long, value_name = "PROVIDER")]
+    from: Option<Origin>,
+
+    /// Configuration file (default: the nearest .mdcr.toml in the current
+    /// directory or its parents)
+    #[arg(long, value_name = "FILE", conflicts_with = "no_config")]
+    config: Option<PathBuf>,
+
+    /// Named profile from the configuration file
+    #[arg(long, value_name = "NAME", conflicts_with = "no_config")]
+    profile: Option<String>,
+
+    /// Ignore .mdcr.toml files
+    #[arg(long)]
+    no_config: bool,
+
+    /// Also recognise [PREFIX:1] citations (repeatable)
+    #[arg(long, value_name = "PREFIX", help_heading = "Vocabulary")]
+    named_prefix: Vec<String>,
+
+    /// Also recognise reference sections headed KEYWORD (repeatable)
+    #[arg(long, value_name = "KEYWORD", help_heading = "Vocabulary")]
+    header_keyword: Vec<String>,
+
+    /// Also recognise the reference headings of LANGUAGE (repeatable): english, german,
+    /// spanish, french, portuguese, italian, dutch, russian, chinese, japanese, korean
+    #[arg(long, value_name = "LANGUAGE", help_heading = "Vocabulary")]
+    language: Vec<Language>,
+
+    /// Verbose output
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// What cleaning removes, for the commands that remove citations
+#[derive(Args)]
+struct Removal {
+    /// What to remove: all, inline-only or references-only
+    #[arg(long, value_name = "MODE", group = "preset", help_heading = "Removal")]
+    mode: Option<RemovalMode>,
+
+    /// Keep reference sections and definitions (same as --mode inline-only)
+    #[arg(
+        long,
+        group = "preset",
+        conflicts_with_all = ["remove_reference_links", "remove_reference_headers", "remove_reference_entries"],
+        help_heading = "Removal"
+    )]
+    keep_references: bool,
+
+    /// Keep inline citations (same as --mode references-only)
+    #[arg(
+        long,
+        group = "preset",
+        conflicts_with = "remove_inline_citations",
+        help_heading = "Removal"
+    )]
+    keep_inline: bool,
+
+    /// Remove inline citations like [1] and [source:1]
+    #[arg(long, help_heading = "Removal")]
+    remove_inline_citations: bool,
+
+    /// Keep inline citations
+    #[arg(
+        long,
+        conflicts_with = "remove_inline_citations",
+        help_heading = "Removal"
+    )]
+    no_remove_inline_citations: bool,
+
+    /// Remove reference link definitions like [1]: https://...
+    #[arg(long, help_heading = "Removal")]
+    remove_reference_links: bool,
+
+    /// Keep reference link definitions
+    #[arg(
+        long,
+        conflicts_with = "remove_reference_links",
+        help_heading = "Removal"
+    )]
+    no_remove_reference_links: bool,
+
+    /// Remove reference section headers like ## References
+    #[arg(long, help_heading = "Removal")]
+    remove_reference_headers: bool,
+
+    /// Keep reference section headers
+    #[arg(
+        long,
+        conflicts_with = "remove_reference_headers",
+        help_heading = "Removal"
+    )]
+    no_remove_reference_headers: bool,
+
+    /// Remove bibliography entries
+    #[arg(long, help_heading = "Removal")]
+    remove_reference_entries: bool,
+
+    /// Keep bibliography entries
+    #[arg(
+        long,
+        conflicts_with = "remove_reference_entries",
+        help_heading = "Removal"
+    )]
+    no_remove_reference_entries: bool,
+
+    /// Tidy the spaces left where citations were removed
+    #[arg(long, help_heading = "Whitespace")]
+    normalize_whitespace: bool,
+
+    /// Leave spaces as they are
+    #[arg(
+        long,
+        conflicts_with = "normalize_whitespace",
+        help_heading = "Whitespace"
+    )]
+    no_normalize_whitespace: bool,
+
+    /// Collapse the blank lines left by removed sections
+    #[arg(long, help_heading = "Whitespace")]
+    remove_blank_lines: bool,
+
+    /// Leave blank lines as they are
+    #[arg(
+        long,
+        conflicts_with = "remove_blank_lines",
+        help_heading = "Whitespace"
+    )]
+    no_remove_blank_lines: bool,
+
+    /// Trim trailing whitespace from each line
+    #[arg(long, help_heading = "Whitespace")]
+    trim_lines: bool,
+
+    /// Leave trailing whitespace
+    #[arg(long, conflicts_with = "trim_lines", help_heading = "Whitespace")]
+    no_trim_lines: bool,
+
+    /// What to put where an inline citation was
+    #[arg(
+        long,
+        value_name = "STYLE",
+        value_parser = ["delete", "html-comment"],
+        help_heading = "Replacement"
+    )]
+    replacement: Option<String>,
+
+    /// Replace each inline citation with TEXT, e.g. †
+    #[arg(
+        long,
+        value_name = "TEXT",
+        conflicts_with = "replacement",
+        help_heading = "Replacement"
+    )]
+    placeholder: Option<String>,
+
+    /// Also remove ARTIFACT (repeatable): content-reference, cite-turn, tracking-params,
+    /// cite-start, copilot-footnotes, copilot-sources, perplexity-chrome
+    #[arg(long, value_name = "ARTIFACT", help_heading = "Artifacts")]
+    artifact: Vec<Artifact>,
+
+    /// Drop the artifact cleanups of the configuration and --from, keeping only
+    /// those given with --artifact
+    #[arg(long, help_heading = "Artifacts")]
+    no_artifacts: bool,
+}
+
+/// Value of `--from`
+#[derive(Clone, Copy)]
+enum Origin {
+    Auto,
+    Provider(Provider),
+}
+
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            Ok(Self::Auto)
+        } else {
+            s.parse().map(Self::Provider)
+        }
+    }
+}
+
+/// Appended to the file stem of outputs written next to their input
+const OUTPUT_SUFFIX: &str = "__cite_removed";
+
+/// Exit code when some input could not be processed, or the arguments or
+/// configuration are invalid
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let settings = cli
+        .command
+        .as_ref()
+        .map_or(&cli.clean.settings, Command::settings);
+    let config = match load_config(settings) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("mdcr: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let args = match &cli.command {
+        None => &cli.clean,
+        Some(Command::Clean(args)) => args,
+        Some(Command::Extract(args)) => return extract::run(args, &config),
+        Some(Command::Check(args)) => return check::run(args, &config),
+        Some(Command::Convert(args)) => return convert::run(args, &config),
+        Some(Command::Stats(args)) => return stats::run(args, &config),
+        Some(Command::Review(args)) => return review::run(args, &config),
+    };
+
+    if let Some(threads) = args.jobs {
+        // Only fails if the pool was already built
+        let _ = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.into())
+            .build_global();
+    }
+    if args.diff {
+        return diff::run(args, &config);
+    }
+    let result = if args.inputs.is_empty() {
+        clean_stdin(args, &config)
+    } else {
+        return clean_files(args, &config);
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mdcr: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Where a cleaned document goes
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Target {
+    Stdout,
+    File(PathBuf),
+    /// The input itself, for `--in-place`
+    InPlace(PathBuf),
+}
+
+impl Target {
+    fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            Self::Stdout
+        } else {
+            Self::File(PathBuf::from(arg))
+        }
+    }
+
+    fn write(&self, text: &str, args: &CleanArgs) -> io::Result<()> {
+        match self {
+            Self::Stdout => io::stdout().lock().write_all(text.as_bytes()),
+            Self::File(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent)?;
+                }
+                write::write_atomic(path, text)
+            }
+            Self::InPlace(path) => InPlace {
+                backup_suffix: args.backup_suffix.clone(),
+                preserve: args.preserve,
+                follow_symlinks: args.follow_symlinks,
+            }
+            .replace(path, text),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdout => f.write_str("stdout"),
+            Self::File(path) | Self::InPlace(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Clean stdin to `--output` or stdout
+fn clean_stdin(args: &CleanArgs, config: &RemoverConfig) -> io::Result<()> {
+    let mut log = Vec::new();
+    if args.settings.verbose {
+        log.push("Reading from stdin...".to_string());
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    let target = args
+        .output
+        .as_deref()
+        .map_or(Target::Stdout, Target::from_arg);
+    let result = clean(args, config, &input, &target, &mut log);
+    for line in &log {
+        eprintln!("{}", line);
+    }
+    result.map(|_| ())
+}
+
+/// Clean every input file, in parallel, printing a summary when there are
+/// several
+fn clean_files(args: &CleanArgs, config: &RemoverConfig) -> ExitCode {
+    let (mut files, errors) = inputs::expand(&args.inputs, args.recursive);
+    if let Some(dir) = args
+        .output_dir
+        .as_deref()
+        .and_then(|d| fs::canonicalize(d).ok())
+    {
+        // Earlier output is not input
+        files.retain(|file| {
+            !file.found_in_directory()
+                || fs::canonicalize(&file.path).map_or(true, |path| !path.starts_with(&dir))
+        });
+    }
+    for error in &errors {
+        eprintln!("mdcr: {}: {}", error.arg, error.message);
+    }
+    if files.len() > 1 {
+        for (flag, set) in [
+            // Several inputs go to stdout one after another, like `cat`
+            ("--output", args.output.as_deref().is_some_and(|o| o != "-")),
+            ("--emit-sources", args.emit_sources.is_some()),
+        ] {
+            if set {
+                eprintln!(
+                    "mdcr: {} needs a single input file, got {}{}",
+                    flag,
+                    files.len(),
+                    if flag == "--output" {
+                        " (use --output-dir)"
+                    } else {
+                        ""
+                    }
+                );
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+
+    let jobs: Vec<_> = match targets(args, &files) {
+        Ok(targets) => files.iter().zip(targets).collect(),
+        Err(message) => {
+            eprintln!("mdcr: {}", message);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let run = |(file, target): &(&InputFile, Target)| {
+        let mut log = Vec::new();
+        let result = clean_file(args, config, file, target, &mut log);
+        (log, result)
+    };
+    // Output to stdout keeps the order of the inputs
+    let outcomes: Vec<_> = if jobs.iter().any(|(_, target)| *target == Target::Stdout) {
+        jobs.iter().map(run).collect()
+    } else {
+        jobs.par_iter().map(run).collect()
+    };
+
+    let summary = jobs.len() > 1;
+    let mut failed = 0;
+    for ((file, target), (log, result)) in jobs.iter().zip(&outcomes) {
+        for line in log {
+            eprintln!("{}", line);
+        }
+        match result {
+            Ok(report) if summary => match target {
+                Target::InPlace(_) => eprintln!(
+                    "{}: removed {} citation(s)",
+                    file.path.display(),
+                    report.counts.citations()
+                ),
+                _ => eprintln!(
+                    "{} -> {}: removed {} citation(s)",
+                    file.path.display(),
+                    target,
+                    report.counts.citations()
+                ),
+            },
+            Ok(_) => {}
+            Err(e) => {
+                failed += 1;
+                eprintln!("mdcr: {}: {}", file.path.display(), e);
+            }
+        }
+    }
+    if summary {
+        eprintln!(
+            "Processed {} file(s): {} cleaned, {} failed",
+            jobs.len(),
+            jobs.len() - failed,
+            failed
+        );
+    }
+
+    if failed > 0 || !errors.is_empty() || jobs.is_empty() {
+        ExitCode::from(EXIT_ERROR)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Output of each file, refusing to overwrite an input or write two files
+/// to the same place
+fn targets(args: &CleanArgs, files: &[InputFile]) -> Result<Vec<Target>, String> {
+    let targets: Vec<_> = files
+        .iter()
+        .map(|file| match (&args.output_dir, &args.output) {
+            _ if args.in_place => Target::InPlace(file.path.clone()),
+            (Some(dir), _) => Target::File(dir.join(file.mirrored())),
+            (None, Some(output)) => Target::from_arg(output),
+            (None, None) => Target::File(suffixed(&file.path)),
+        })
+        .collect();
+
+    let mut claimed = HashMap::new();
+    for (file, target) in files.iter().zip(&targets) {
+        let Target::File(path) = target else {
+            continue;
+        };
+        if same_file(path, &file.path) {
+            return Err(format!(
+                "{}: the output would overwrite the input",
+                file.path.display()
+            ));
+        }
+        if let Some(other) = claimed.insert(path.clone(), &file.path) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other.display(),
+                file.path.display(),
+                path.display()
+            ));
+        }
+    }
+    Ok(targets)
+}
+
+/// `dir/name__cite_removed.ext` for `dir/name.ext`
+pub(crate) fn suffixed(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("md");
+    path.with_file_name(format!("{}{}.{}", stem, OUTPUT_SUFFIX, ext))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn clean_file(
+    args: &CleanArgs,
+    config: &RemoverConfig,
+    file: &InputFile,
+    target: &Target,
+    log: &mut Vec<String>,
+) -> io::Result<RemovalReport> {
+    if args.settings.verbose {
+        log.push(format!("Reading from file: {}", file.path.display()));
+    }
+    let input = fs::read_to_string(&file.path)?;
+    clean(args, config, &input, target, log)
+}
+
+/// Export the sources, clean `input` and write it to `target`
+fn clean(
+    args: &CleanArgs,
+    config: &RemoverConfig,
+    input: &str,
+    target: &Target,
+    log: &mut Vec<String>,
+) -> io::Result<RemovalReport> {
+    let config = resolve_config(&args.settings, config, input, log);
+    let remover = CitationRemover::with_config(apply_removal(&args.removal, config));
+
+    // Export sources before they are removed
+    if let Some(path) = &args.emit_sources {
+        let analysis = remover.analyze(input);
+        if args.settings.verbose {
+            log.push(format!(
+                "Writing {} source(s) as {} to: {}",
+                analysis.sources.len(),
+                args.sources_format,
+                path
+            ));
+        }
+        fs::write(path, analysis.export_sources(args.sources_format))?;
+    }
+
+    // Remove citations
+    if args.settings.verbose {
+        log.push(format!(
+            "Removing citations (input size: {} bytes)...",
+            input.len()
+        ));
+    }
+    let (result, report) = remover.remove_with_report(input);
+
+    if args.settings.verbose {
+        log.extend(report_lines(&report));
+        log.push(format!(
+            "Citations removed (output size: {} bytes)",
+            result.len()
+        ));
+    }
+    if matches!(target, Target::InPlace(_)) && result == input {
+        if args.settings.verbose {
+            log.push("Unchanged; not rewriting".to_string());
+        }
+        return Ok(report);
+    }
+    if args.settings.verbose {
+        log.push(format!("Writing to {}", target));
+    }
+    target.write(&result, args)?;
+    if args.settings.verbose {
+        log.push("Done!".to_string());
+    }
+    Ok(report)
+}
+
+/// `config` for `input`, with the provider of `--from` and the vocabulary
+/// flags applied
+fn resolve_config(
+    settings: &Settings,
+    config: &RemoverConfig,
+    input: &str,
+    log: &mut Vec<String>,
+) -> RemoverConfig {
+    let config = match settings.from {
+        None => config.clone(),
+        Some(Origin::Provider(provider)) => config.clone().with_provider(provider),
+        Some(Origin::Auto) => match Provider::detect(input) {
+            Some(provider) => {
+                if settings.verbose {
+                    log.push(format!("Detected source: {}", provider));
+                }
+                config.clone().with_provider(provider)
+            }
+            _ => {
+                if settings.verbose {
+                    log.push("No source detected; using the configured settings".to_string());
+                }
+                config.clone()
+            }
+        },
+    };
+    apply_vocabulary(settings, config)
+}
+
+/// The configuration from `--config` or the nearest `.mdcr.toml`, with
+/// `--profile` applied
+fn load_config(settings: &Settings) -> Result<RemoverConfig, RemoverError> {
+    let path = match &settings.config {
+        Some(path) => Some(path.clone()),
+        None if settings.no_config => None,
+        None => ConfigFile::find(std::env::current_dir()?),
+    };
+    let Some(path) = path else {
+        return match &settings.profile {
+            Some(name) => Err(RemoverError::Config(format!(
+                "profile '{}' requested but no {} was found",
+                name,
+                ConfigFile::FILE_NAME
+            ))),
+            None => Ok(RemoverConfig::default()),
+        };
+    };
+
+    if settings.verbose {
+        eprintln!("Using config: {}", path.display());
+    }
+    ConfigFile::load(&path)?.config(settings.profile.as_deref())
+}
+
+/// `config` with the removal, whitespace, replacement and artifact flags
+/// applied, in that order
+fn apply_removal(removal: &Removal, mut config: RemoverConfig) -> RemoverConfig {
+    let mode = if removal.keep_references {
+        Some(RemovalMode::InlineOnly)
+    } else if removal.keep_inline {
+        Some(RemovalMode::ReferencesOnly)
+    } else {
+        removal.mode
+    };
+    if let Some(mode) = mode {
+        config = config.with_mode(mode);
+    }
+
+    let switches = [
+        (
+            removal.remove_inline_citations,
+            removal.no_remove_inline_citations,
+            &mut config.remove_inline_citations,
+        ),
+        (
+            removal.remove_reference_links,
+            removal.no_remove_reference_links,
+            &mut config.remove_reference_links,
+        ),
+        (
+            removal.remove_reference_headers,
+            removal.no_remove_reference_headers,
+            &mut config.remove_reference_headers,
+        ),
+        (
+            removal.remove_reference_entries,
+            removal.no_remove_reference_entries,
+            &mut config.remove_reference_entries,
+        ),
+        (
+            removal.normalize_whitespace,
+            removal.no_normalize_whitespace,
+            &mut config.normalize_whitespace,
+        ),
+        (
+            removal.remove_blank_lines,
+            removal.no_remove_blank_lines,
+            &mut config.remove_blank_lines,
+        ),
+        (
+            removal.trim_lines,
+            removal.no_trim_lines,
+            &mut config.trim_lines,
+        ),
+    ];
+    // clap rejects a flag together with its --no- form
+    for (on, off, switch) in switches {
+        if on || off {
+            *switch = on;
+        }
+    }
+
+    match (removal.replacement.as_deref(), &removal.placeholder) {
+        (Some("html-comment"), _) => config.replacement = Replacement::HtmlComment,
+        (Some(_), _) => config.replacement = Replacement::Delete,
+        (None, Some(text)) => config.replacement = Replacement::Text(text.clone()),
+        (None, None) => {}
+    }
+
+    if removal.no_artifacts {
+        config.artifacts.clear();
+    }
+    for &artifact in &removal.artifact {
+        if !config.artifacts.contains(&artifact) {
+            config.artifacts.push(artifact);
+        }
+    }
+    config
+}
+
+/// `config` with the vocabulary flags applied
+fn apply_vocabulary(settings: &Settings, mut config: RemoverConfig) -> RemoverConfig {
+    for prefix in &settings.named_prefix {
+        config = config.with_named_prefix(prefix.as_str());
+    }
+    for keyword in &settings.header_keyword {
+        config = config.with_header_keyword(keyword.as_str());
+    }
+    for &language in &settings.language {
+        config = config.with_language(language);
+    }
+    config
+}
+
+/// Every citation removal and the totals per category
+fn report_lines(report: &RemovalReport) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut removals: Vec<_> = report
+        .removals
+        .iter()
+        .filter(|r| r.rule.category() != RemovalCategory::Whitespace)
+        .collect();
+    removals.sort_by_key(|r| r.range.start);
+
+    for removal in removals {
+        let text = removal.text.trim_end();
+        let snippet: String = text.chars().take(60).collect();
+        let ellipsis = if snippet.len() < text.len() {
+            "…"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "  {}:{}  {:<20} {:?}{}",
+            removal.line, removal.column, removal.rule, snippet, ellipsis
+        ));
+    }
+
+    let counts = &report.counts;
+    lines.push(format!(
+        "Removed {} inline citation(s), {} definition(s), {} header(s), {} entr{}, {} artifact(s); {} whitespace fix(es)",
+        counts.inline,
+        counts.definitions,
+        counts.headers,
+        counts.entries,
+        if counts.entries == 1 { "y" } else { "ies" },
+        counts.artifacts,
+        counts.whitespace
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(args: &[&str]) -> RemoverConfig {
+        let cli = Cli::try_parse_from(["mdcr", "--no-config"].iter().chain(args)).unwrap();
+        let config = apply_removal(&cli.clean.removal, RemoverConfig::default());
+        apply_vocabulary(&cli.clean.settings, config)
+    }
+
+    fn rejects(args: &[&str]) -> bool {
+        Cli::try_parse_from(["mdcr"].iter().chain(args)).is_err()
+    }
+
+    #[test]
+    fn test_presets_and_switches() {
+        let keep = config(&["--keep-references"]);
+        assert!(keep.remove_inline_citations && !keep.remove_reference_links);
+        assert!(!config(&["--mode", "references-only"]).remove_inline_citations);
+
+        // Switches refine the preset
+        let refined = config(&["--mode", "inline-only", "--remove-reference-headers"]);
+        assert!(refined.remove_reference_headers && !refined.remove_reference_entries);
+
+        let tidy = config(&["--no-trim-lines", "--no-remove-blank-lines"]);
+        assert!(!tidy.trim_lines && !tidy.remove_blank_lines && tidy.normalize_whitespace);
+    }
+
+    #[test]
+    fn test_replacement_vocabulary_and_artifacts() {
+        assert!(matches!(
+            config(&["--placeholder", "†"]).replacement,
+            Replacement::Text(ref text) if text == "†"
+        ));
+        assert!(matches!(
+            config(&["--replacement", "html-comment"]).replacement,
+            Replacement::HtmlComment
+        ));
+
+        let vocabulary = config(&["--named-prefix", "citation", "--language", "german"]);
+        assert!(vocabulary.named_prefixes.contains(&"citation".to_string()));
+        assert!(vocabulary.header_keywords.contains(&"Quellen".to_string()));
+
+        let cli = Cli::try_parse_from(["mdcr", "--no-artifacts", "--artifact", "tracking-params"])
+            .unwrap();
+        let only = apply_removal(
+            &cli.clean.removal,
+            RemoverConfig::for_provider(Provider::ChatGpt),
+        );
+        assert_eq!(only.artifacts, [Artifact::TrackingParams]);
+    }
+
+    #[test]
+    fn test_conflicting_flags_are_rejected() {
+        assert!(rejects(&["--trim-lines", "--no-trim-lines"]));
+        assert!(rejects(&["--mode", "all", "--keep-inline"]));
+        assert!(rejects(&["--keep-references", "--remove-reference-links"]));
+        assert!(rejects(&["--keep-inline", "--remove-inline-citations"]));
+        assert!(rejects(&["--placeholder", "x", "--replacement", "delete"]));
+        assert!(rejects(&["--no-config", "--profile", "strict"]));
+        assert!(rejects(&["--mode", "sometimes"]));
+        assert!(rejects(&["--stat", "a.md"]));
+        assert!(rejects(&["--diff", "--in-place", "a.md"]));
+        assert!(rejects(&["--diff", "--emit-sources", "refs.json", "a.md"]));
+    }
+
+    #[test]
+    fn test_check_command() {
+        let cli = Cli::try_parse_from([
+            "mdcr",
+            "check",
+            "--format",
+            "sarif",
+            "--keep-inline",
+            "a.md",
+        ])
+        .unwrap();
+        let Some(Command::Check(args)) = cli.command else {
+            panic!("expected the check command");
+        };
+        assert_eq!(args.format, check::Format::Sarif);
+        assert_eq!(args.inputs, ["a.md"]);
+        assert!(args.removal.keep_inline);
+
+        assert!(Cli::try_parse_from(["mdcr", "a.md"])
+            .unwrap()
+            .command
+            .is_none());
+        assert!(rejects(&["check", "--in-place", "a.md"]));
+        assert!(rejects(&["check", "--format", "xml"]));
+    }
+
+    #[test]
+    fn test_clean_is_the_default_command() {
+        let cli = Cli::try_parse_from(["mdcr", "clean", "--keep-inline", "a.md"]).unwrap();
+        let Some(Command::Clean(args)) = cli.command else {
+            panic!("expected the clean command");
+        };
+        let default = Cli::try_parse_from(["mdcr", "--keep-inline", "a.md"]).unwrap();
+        assert_eq!(args.inputs, default.clean.inputs);
+        assert!(args.removal.keep_inline && default.clean.removal.keep_inline);
+
+        // After an option, a command name is an input
+        let file = Cli::try_parse_from(["mdcr", "--keep-inline", "stats"]).unwrap();
+        assert!(file.command.is_none());
+        assert_eq!(file.clean.inputs, ["stats"]);
+        assert!(rejects(&["extract", "--diff", "a.md"]));
+    }
+
+    #[test]
+    fn test_removal_flags_only_apply_to_removing_commands() {
+        let cli = Cli::try_parse_from(["mdcr", "review", "--mode", "inline-only", "a.md"]).unwrap();
+        let Some(Command::Review(args)) = cli.command else {
+            panic!("expected the review command");
+        };
+        assert_eq!(args.removal.mode, Some(RemovalMode::InlineOnly));
+
+        assert!(rejects(&["extract", "--keep-inline", "a.md"]));
+        assert!(rejects(&["stats", "--mode", "all", "a.md"]));
+        assert!(rejects(&["convert", "--placeholder", "x", "a.md"]));
+        assert!(rejects(&["extract", "--artifact", "cite-turn", "a.md"]));
+        assert!(!rejects(&[
+            "extract",
+            "--from",
+            "chatgpt",
+            "--language",
+            "german",
+            "a.md"
+        ]));
+    }
+
+    #[test]
+    fn test_extract_convert_and_stats_commands() {
+        let cli = Cli::try_parse_from(["mdcr", "extract", "--format", "bibtex", "a.md"]).unwrap();
+        let Some(Command::Extract(args)) = cli.command else {
+            panic!("expected the extract command");
+        };
+        assert_eq!(args.format, extract::Format::BibTex);
+        assert_eq!(args.format.sources(), Some(SourcesFormat::BibTex));
+        assert!(!rejects(&["extract", "--format", "CSL-JSON", "a.md"]));
+        assert!(rejects(&["extract", "--format", "bib-tex", "a.md"]));
+
+        let cli = Cli::try_parse_from([
+            "mdcr",
+            "convert",
+            "--to",
+            "inline-links",
+            "--link-style",
+            "superscript",
+            "a.md",
+        ])
+        .unwrap();
+        let Some(Command::Convert(args)) = cli.command else {
+            panic!("expected the convert command");
+        };
+        assert_eq!(args.to, convert::Style::InlineLinks);
+        assert_eq!(args.link_style, Some(LinkStyle::Superscript));
+        assert!(rejects(&["convert", "-i"]));
+        assert!(rejects(&["convert", "-r", "docs"]));
+        assert!(rejects(&["convert", "--follow-symlinks", "a.md"]));
+        assert!(!rejects(&["convert", "-i", "--follow-symlinks", "a.md"]));
+        assert!(rejects(&["convert", "--to", "endnotes", "a.md"]));
+
+        let cli = Cli::try_parse_from(["mdcr", "stats", "--top", "5", "-r", "docs"]).unwrap();
+        let Some(Command::Stats(args)) = cli.command else {
+            panic!("expected the stats command");
+        };
+        assert_eq!((args.top, args.format), (5, stats::Format::Human));
+        assert!(args.recursive);
+    }
+
+    #[test]
+    fn test_review_command() {
+        let cli = Cli::try_parse_from(["mdcr", "review", "a.md", "--from", "chatgpt"]).unwrap();
+        let Some(Command::Review(args)) = cli.command else {
+            panic!("expected the review command");
+        };
+        assert_eq!(args.file, Path::new("a.md"));
+        assert!(args.settings.from.is_some());
+        assert!(!args.follow_symlinks);
+
+        assert!(rejects(&["review"]));
+        assert!(rejects(&["review", "a.md", "b.md"]));
+        assert!(rejects(&[
+            "review",
+            "a.md",
+            "-o",
+            "b.md",
+            "--backup-suffix",
+            ".bak"
+        ]));
+        assert!(rejects(&[
+            "review",
+            "a.md",
+            "-o",
+            "b.md",
+            "--follow-symlinks"
+        ]));
+    }
+
+    #[test]
+    fn test_stdout_takes_several_inputs() {
+        let dir = std::env::temp_dir().join(format!("mdcr-stdout-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = ["a.md", "b.md"].map(|name| dir.join(name).to_str().unwrap().to_string());
+        for path in &paths {
+            fs::write(path, "Text[1].\n").unwrap();
+        }
+
+        let cli = Cli::try_parse_from(["mdcr", &paths[0], &paths[1], "-o", "-"]).unwrap();
+        let (files, errors) = inputs::expand(&cli.clean.inputs, false);
+        assert!(errors.is_empty());
+        let targets = targets(&cli.clean, &files).unwrap();
+        assert!(targets.len() == 2 && targets.iter().all(|t| *t == Target::Stdout));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_in_place_leaves_clean_files_alone() {
+        let dir = std::env::temp_dir().join(format!("mdcr-clean-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("c3.md");
+        fs::write(&path, "Clean text.\n").unwrap();
+
+        let file = path.to_str().unwrap();
+        let cli =
+            Cli::try_parse_from(["mdcr", "--no-config", "-i", "--backup-suffix", ".bak", file])
+                .unwrap();
+        let target = Target::InPlace(path.clone());
+        let config = RemoverConfig::default();
+        let report = clean(
+            &cli.clean,
+            &config,
+            "Clean text.\n",
+            &target,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert!(report.removals.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Clean text.\n");
+        assert!(!dir.join("c3.md.bak").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
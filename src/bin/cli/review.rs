@@ -0,0 +1,561 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use markdown_ai_cite_remove::{CitationRemover, Removal, RemovalCategory, RemoverConfig};
+
+use crate::write::{self, InPlace};
+use crate::{apply_removal, resolve_config, ReviewArgs, EXIT_ERROR};
+
+/// Lines shown before and after a removal
+const CONTEXT_LINES: usize = 3;
+
+/// What the reviewer decided about one removal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Undecided,
+    Accepted,
+    Rejected,
+}
+
+/// The removals proposed for a document, and the decisions made so far
+pub struct Review {
+    input: String,
+    removals: Vec<Removal>,
+    decisions: Vec<Decision>,
+    cursor: usize,
+}
+
+impl Review {
+    /// Every removal `config` would make in `input`, in input order
+    ///
+    /// Whitespace fixes are not reviewed; they are redone around the accepted
+    /// removals.
+    pub fn new(input: String, config: &RemoverConfig) -> Self {
+        let (_, report) = CitationRemover::with_config(config.clone()).remove_with_report(&input);
+        let mut removals: Vec<_> = report
+            .removals
+            .into_iter()
+            .filter(|r| r.rule.category() != RemovalCategory::Whitespace)
+            .collect();
+        removals.sort_by_key(|r| r.range.start);
+        let decisions = vec![Decision::Undecided; removals.len()];
+        Self {
+            input,
+            removals,
+            decisions,
+            cursor: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.removals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removals.is_empty()
+    }
+
+    /// Index of the removal under review
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn current(&self) -> &Removal {
+        &self.removals[self.cursor]
+    }
+
+    pub fn decision(&self) -> Decision {
+        self.decisions[self.cursor]
+    }
+
+    /// Number of removals with `decision`
+    pub fn count(&self, decision: Decision) -> usize {
+        self.decisions.iter().filter(|&&d| d == decision).count()
+    }
+
+    pub fn next(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len() - 1);
+    }
+
+    pub fn previous(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Decide about the current removal and move to the next one
+    pub fn decide(&mut self, decision: Decision) {
+        self.decisions[self.cursor] = decision;
+        self.next();
+    }
+
+    /// Decide about the current removal and every undecided one made by the
+    /// same rule, and move to the next undecided removal
+    pub fn decide_rule(&mut self, decision: Decision) {
+        let rule = self.current().rule.clone();
+        self.decisions[self.cursor] = decision;
+        for (removal, d) in self.removals.iter().zip(&mut self.decisions) {
+            if removal.rule == rule && *d == Decision::Undecided {
+                *d = decision;
+            }
+        }
+        if let Some(next) = (self.cursor..self.len())
+            .chain(0..self.cursor)
+            .find(|&i| self.decisions[i] == Decision::Undecided)
+        {
+            self.cursor = next;
+        }
+    }
+
+    /// The input with the accepted removals made and whitespace tidied up as
+    /// `config` says
+    pub fn result(&self, config: &RemoverConfig) -> String {
+        let mut text = String::with_capacity(self.input.len());
+        let mut at = 0;
+        for (removal, &decision) in self.removals.iter().zip(&self.decisions) {
+            if decision != Decision::Accepted || removal.range.start < at {
+                continue;
+            }
+            text.push_str(&self.input[at..removal.range.start]);
+            text.push_str(&removal.replacement);
+            at = removal.range.end;
+        }
+        text.push_str(&self.input[at..]);
+
+        let tidy = RemoverConfig {
+            remove_inline_citations: false,
+            remove_reference_links: false,
+            remove_reference_headers: false,
+            remove_reference_entries: false,
+            artifacts: Vec::new(),
+            ..config.clone()
+        };
+        CitationRemover::with_config(tidy).remove(&text)
+    }
+
+    /// The lines around the current removal, split into what stays, what
+    /// is removed and what is put in its place
+    pub fn context(&self) -> Vec<(usize, Vec<Span>)> {
+        let removal = self.current();
+        let range = &removal.range;
+        let last_line = removal.line
+            + self.input[range.clone()]
+                .trim_end_matches('\n')
+                .matches('\n')
+                .count();
+        let first = removal.line.saturating_sub(CONTEXT_LINES).max(1);
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (i, line) in self.input.split_inclusive('\n').enumerate() {
+            let number = i + 1;
+            let end = start + line.len();
+            if number > last_line + CONTEXT_LINES {
+                break;
+            }
+            if number >= first {
+                let content = start..start + line.trim_end_matches(['\n', '\r']).len();
+                let mut spans = Vec::new();
+                let removed = range.start.max(content.start)..range.end.min(content.end);
+                if removed.start < removed.end
+                    || (range.is_empty() && content.contains(&range.start))
+                {
+                    spans.push(Span::Text(
+                        self.input[content.start..removed.start].to_string(),
+                    ));
+                    spans.push(Span::Removed(self.input[removed.clone()].to_string()));
+                    if range.end <= end && !removal.replacement.is_empty() {
+                        spans.push(Span::Replacement(removal.replacement.clone()));
+                    }
+                    spans.push(Span::Text(self.input[removed.end..content.end].to_string()));
+                } else if (content.start..end).contains(&range.start) {
+                    // Only the line break is removed
+                    spans.push(Span::Text(self.input[content].to_string()));
+                    spans.push(Span::Removed("↵".to_string()));
+                } else {
+                    spans.push(Span::Text(self.input[content].to_string()));
+                }
+                spans.retain(|span| !span.text().is_empty());
+                lines.push((number, spans));
+            }
+            start = end;
+        }
+        lines
+    }
+}
+
+/// Part of a context line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Span {
+    Text(String),
+    Removed(String),
+    Replacement(String),
+}
+
+impl Span {
+    fn text(&self) -> &str {
+        match self {
+            Self::Text(text) | Self::Removed(text) | Self::Replacement(text) => text,
+        }
+    }
+
+    fn with_text(&self, text: String) -> Self {
+        match self {
+            Self::Text(_) => Self::Text(text),
+            Self::Removed(_) => Self::Removed(text),
+            Self::Replacement(_) => Self::Replacement(text),
+        }
+    }
+}
+
+/// `spans` broken into rows of at most `width` characters
+fn wrap(spans: &[Span], width: usize) -> Vec<Vec<Span>> {
+    let mut rows = vec![Vec::new()];
+    let mut used = 0;
+    for span in spans {
+        let mut chars = span.text().chars().peekable();
+        while chars.peek().is_some() {
+            if used == width {
+                rows.push(Vec::new());
+                used = 0;
+            }
+            let part: String = chars.by_ref().take(width - used).collect();
+            used += part.chars().count();
+            rows.last_mut().unwrap().push(span.with_text(part));
+        }
+    }
+    rows
+}
+
+/// Review the removals in `args.file` in the terminal, and write the result
+pub fn run(args: &ReviewArgs, config: &RemoverConfig) -> ExitCode {
+    match review(args, config) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mdcr: {}: {}", args.file.display(), e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn review(args: &ReviewArgs, config: &RemoverConfig) -> io::Result<()> {
+    let input = fs::read_to_string(&args.file)?;
+    let mut log = Vec::new();
+    let config = resolve_config(&args.settings, config, &input, &mut log);
+    let config = apply_removal(&args.removal, config);
+    for line in &log {
+        eprintln!("{}", line);
+    }
+    let mut review = Review::new(input, &config);
+    if review.is_empty() {
+        eprintln!("{}: nothing to remove", args.file.display());
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "mdcr review needs a terminal",
+        ));
+    }
+    // Refuse before the session rather than lose its decisions
+    let in_place = InPlace {
+        backup_suffix: args.backup_suffix.clone(),
+        follow_symlinks: args.follow_symlinks,
+        ..Default::default()
+    };
+    match &args.output {
+        Some(path) => write::check_writable(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+        None => in_place.check(&args.file)?,
+    }
+
+    let name = args.file.display().to_string();
+    let write = {
+        let _screen = Screen::enter()?;
+        interact(&mut review, &name)?
+    };
+    if !write {
+        eprintln!("{}: quit without writing", name);
+        return Ok(());
+    }
+
+    let accepted = review.count(Decision::Accepted);
+    if accepted == 0 {
+        eprintln!("{}: no removals accepted; nothing written", name);
+        return Ok(());
+    }
+    let result = review.result(&config);
+    let target = match &args.output {
+        Some(path) => {
+            write::write_atomic(path, &result)?;
+            path.display().to_string()
+        }
+        None => {
+            in_place.replace(&args.file, &result)?;
+            name
+        }
+    };
+    eprintln!(
+        "Wrote {}: made {} of {} removal(s)",
+        target,
+        accepted,
+        review.len()
+    );
+    Ok(())
+}
+
+/// Handle keys until the user writes (`true`) or quits (`false`)
+fn interact(review: &mut Review, name: &str) -> io::Result<bool> {
+    let mut out = io::stdout();
+    loop {
+        draw(&mut out, review, name)?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false)
+            }
+            KeyCode::Char('y') => review.decide(Decision::Accepted),
+            KeyCode::Char('n') => review.decide(Decision::Rejected),
+            KeyCode::Char('a') => review.decide_rule(Decision::Accepted),
+            KeyCode::Char('r') => review.decide_rule(Decision::Rejected),
+            KeyCode::Right | KeyCode::Down | KeyCode::Char('j' | 'l' | ' ') => review.next(),
+            KeyCode::Left | KeyCode::Up | KeyCode::Char('k' | 'h') => review.previous(),
+            KeyCode::Char('w') => return Ok(true),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// Raw mode on the alternate screen, restored when dropped
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Self;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(out: &mut impl Write, review: &Review, name: &str) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let (width, height) = (usize::from(width).max(20), usize::from(height).max(8));
+    let removal = review.current();
+    let rule = removal.rule.as_str();
+    queue!(out, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+
+    let (status, color) = match review.decision() {
+        Decision::Undecided => ("undecided", Color::Yellow),
+        Decision::Accepted => ("accept", Color::Green),
+        Decision::Rejected => ("reject", Color::Red),
+    };
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print(format!(
+            "{}  {}/{}  {}:{}  {}  ",
+            name,
+            review.position() + 1,
+            review.len(),
+            removal.line,
+            removal.column,
+            rule
+        )),
+        SetForegroundColor(color),
+        Print(status),
+        ResetColor,
+        SetAttribute(Attribute::Reset),
+        cursor::MoveTo(0, 1),
+        Print(format!(
+            "{} accepted, {} rejected, {} undecided",
+            review.count(Decision::Accepted),
+            review.count(Decision::Rejected),
+            review.count(Decision::Undecided)
+        )),
+    )?;
+
+    // Context, wrapped, scrolled so the removal starts near the top
+    let gutter = 7;
+    let mut rows = Vec::new();
+    for (number, spans) in review.context() {
+        for (i, row) in wrap(&spans, width - gutter).into_iter().enumerate() {
+            rows.push((if i == 0 { Some(number) } else { None }, row));
+        }
+    }
+    let budget = height - 5;
+    let first_removed = rows
+        .iter()
+        .position(|(_, row)| row.iter().any(|s| matches!(s, Span::Removed(_))))
+        .unwrap_or_default();
+    let skip = first_removed.saturating_sub(CONTEXT_LINES.min(budget / 3));
+    for (y, (number, row)) in rows.iter().skip(skip).take(budget).enumerate() {
+        let gutter = number.map_or(String::new(), |n| n.to_string());
+        queue!(
+            out,
+            cursor::MoveTo(0, (y + 3) as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(format!("{:>5} ", gutter)),
+            ResetColor,
+        )?;
+        for span in row {
+            match span {
+                Span::Text(text) => queue!(out, Print(text))?,
+                Span::Removed(text) => queue!(
+                    out,
+                    SetForegroundColor(Color::Red),
+                    SetAttribute(Attribute::CrossedOut),
+                    Print(text),
+                    SetAttribute(Attribute::Reset),
+                    ResetColor
+                )?,
+                Span::Replacement(text) => queue!(
+                    out,
+                    SetForegroundColor(Color::Black),
+                    SetBackgroundColor(Color::Green),
+                    Print(text),
+                    ResetColor
+                )?,
+            }
+        }
+    }
+    if rows.len() > skip + budget {
+        queue!(
+            out,
+            cursor::MoveTo(0, (budget + 3) as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(format!(
+                "      … {} more row(s)",
+                rows.len() - skip - budget
+            )),
+            ResetColor
+        )?;
+    }
+
+    queue!(
+        out,
+        cursor::MoveTo(0, (height - 1) as u16),
+        SetAttribute(Attribute::Reverse),
+        Print(format!(
+            "{:<width$}",
+            format!(
+                " y/n accept/reject  a/r all {}  ←/→ move  w write  q quit",
+                rule
+            ),
+            width = width
+        )),
+        SetAttribute(Attribute::Reset),
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use markdown_ai_cite_remove::Provider;
+
+    const INPUT: &str = "# Title\n\nFact[1] and more[2].\n\nMore citeturn0search0 text.\n\n## References\n\n[1]: https://a.example\n[2]: https://b.example\n";
+
+    #[test]
+    fn test_accepting_everything_matches_cleaning() {
+        let config = RemoverConfig::for_provider(Provider::ChatGpt);
+        let mut review = Review::new(INPUT.to_string(), &config);
+        assert!(review.len() >= 4);
+        review.decide_rule(Decision::Accepted);
+        while review.count(Decision::Undecided) > 0 {
+            review.decide_rule(Decision::Accepted);
+        }
+        assert_eq!(
+            review.result(&config),
+            CitationRemover::with_config(config.clone()).remove(INPUT)
+        );
+    }
+
+    #[test]
+    fn test_rejected_removals_are_kept() {
+        let config = RemoverConfig::default();
+        let mut review = Review::new(
+            "Fact[1] and more[2].\n\n[1]: https://a.example\n".to_string(),
+            &config,
+        );
+        let rules: Vec<_> = review.removals.iter().map(|r| r.rule.as_str()).collect();
+        assert_eq!(
+            rules,
+            ["inline-numeric", "inline-numeric", "link-definition"]
+        );
+
+        review.decide(Decision::Rejected);
+        review.decide(Decision::Accepted);
+        review.decide(Decision::Accepted);
+        assert_eq!(review.position(), 2);
+        assert_eq!(review.result(&config), "Fact[1] and more.\n");
+
+        // Deciding for a rule keeps earlier decisions, and moves on to the
+        // next undecided removal
+        let mut review = Review::new(
+            "Fact[1] and more[2][3].\n\n[1]: https://a.example\n".to_string(),
+            &config,
+        );
+        review.decide(Decision::Accepted);
+        review.decide_rule(Decision::Rejected);
+        assert_eq!(review.position(), 3);
+        assert_eq!(review.count(Decision::Rejected), 2);
+        review.decide(Decision::Accepted);
+        assert_eq!(review.result(&config), "Fact and more[2][3].\n");
+    }
+
+    #[test]
+    fn test_context_marks_the_removal() {
+        let config = RemoverConfig {
+            replacement: markdown_ai_cite_remove::Replacement::Text("†".into()),
+            ..Default::default()
+        };
+        let review = Review::new(
+            "One\nTwo\nThree\nFour\nFact[1] here.\nSix\n".to_string(),
+            &config,
+        );
+        let context = review.context();
+        let numbers: Vec<_> = context.iter().map(|(n, _)| *n).collect();
+        assert_eq!(numbers, [2, 3, 4, 5, 6]);
+        assert_eq!(
+            context[3].1,
+            [
+                Span::Text("Fact".into()),
+                Span::Removed("[1]".into()),
+                Span::Replacement("†".into()),
+                Span::Text(" here.".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let spans = [Span::Text("abcd".into()), Span::Removed("efg".into())];
+        assert_eq!(
+            wrap(&spans, 3),
+            [
+                vec![Span::Text("abc".into())],
+                vec![Span::Text("d".into()), Span::Removed("ef".into())],
+                vec![Span::Removed("g".into())],
+            ]
+        );
+    }
+}
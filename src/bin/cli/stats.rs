@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use markdown_ai_cite_remove::{CitationAnalysis, CitationRemover, RemoverConfig};
+use serde_json::json;
+
+use crate::{inputs, resolve_config, StatsArgs, EXIT_ERROR};
+
+/// How `mdcr stats` prints its report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+/// Citation counts of one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileStats {
+    pub file: String,
+    /// Inline citation markers
+    pub citations: usize,
+    /// Markers without a matching source
+    pub unresolved: usize,
+    /// Definitions and bibliography entries
+    pub sources: usize,
+    /// Sources no marker refers to
+    pub unused: usize,
+    /// Domain of each source with a URL, and the number of markers citing it
+    pub domains: Vec<(String, usize)>,
+}
+
+impl FileStats {
+    pub fn new(file: &str, analysis: &CitationAnalysis) -> Self {
+        Self {
+            file: file.to_string(),
+            citations: analysis.citations.len(),
+            unresolved: analysis.unresolved().count(),
+            sources: analysis.sources.len(),
+            unused: analysis.unused().count(),
+            domains: analysis
+                .sources
+                .iter()
+                .filter_map(|source| Some((source.domain()?, source.citations.len())))
+                .collect(),
+        }
+    }
+}
+
+/// How often a domain is cited across the corpus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainCount {
+    pub domain: String,
+    /// Sources pointing to the domain
+    pub sources: usize,
+    /// Inline citations of those sources
+    pub citations: usize,
+    /// Files with such a source
+    pub files: usize,
+}
+
+/// Domains by citations, then sources, then name
+pub fn domains(stats: &[FileStats]) -> Vec<DomainCount> {
+    let mut counts: HashMap<&str, DomainCount> = HashMap::new();
+    for file in stats {
+        let mut seen = Vec::new();
+        for (domain, citations) in &file.domains {
+            let count = counts.entry(domain).or_insert_with(|| DomainCount {
+                domain: domain.clone(),
+                sources: 0,
+                citations: 0,
+                files: 0,
+            });
+            count.sources += 1;
+            count.citations += citations;
+            if !seen.contains(&domain) {
+                seen.push(domain);
+                count.files += 1;
+            }
+        }
+    }
+    let mut domains: Vec<_> = counts.into_values().collect();
+    domains.sort_by(|a, b| {
+        (b.citations, b.sources)
+            .cmp(&(a.citations, a.sources))
+            .then_with(|| a.domain.cmp(&b.domain))
+    });
+    domains
+}
+
+/// Report citation counts and cited domains across the inputs of
+/// `mdcr stats`, or stdin
+pub fn run(args: &StatsArgs, config: &RemoverConfig) -> ExitCode {
+    let settings = &args.settings;
+    let (stats, ok) = inputs::map_texts(&args.inputs, args.recursive, |name, input, log| {
+        let config = resolve_config(settings, config, input, log);
+        FileStats::new(name, &CitationRemover::with_config(config).analyze(input))
+    });
+
+    let text = match args.format {
+        Format::Human => human(&stats, args.top),
+        Format::Json => json(&stats, args.top),
+    };
+    if let Err(e) = io::stdout().lock().write_all(text.as_bytes()) {
+        eprintln!("mdcr: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_ERROR)
+    }
+}
+
+/// At most `top` domains, or all if `top` is 0
+fn top_domains(stats: &[FileStats], top: usize) -> Vec<DomainCount> {
+    let mut domains = domains(stats);
+    if top > 0 {
+        domains.truncate(top);
+    }
+    domains
+}
+
+/// Totals and a table of the most cited domains
+pub fn human(stats: &[FileStats], top: usize) -> String {
+    let total = |count: fn(&FileStats) -> usize| stats.iter().map(count).sum::<usize>();
+    let with_citations = stats.iter().filter(|s| s.citations + s.sources > 0).count();
+    let mut text = format!(
+        "Files:              {} ({} with citations)\n\
+         Inline citations:   {} ({} unresolved)\n\
+         Sources:            {} ({} unused)\n",
+        stats.len(),
+        with_citations,
+        total(|s| s.citations),
+        total(|s| s.unresolved),
+        total(|s| s.sources),
+        total(|s| s.unused),
+    );
+
+    let domains = top_domains(stats, top);
+    if !domains.is_empty() {
+        let width = domains
+            .iter()
+            .map(|d| d.domain.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("Domain".len());
+        text.push_str(&format!(
+            "\n{:<width$}  Citations  Sources  Files\n",
+            "Domain",
+            width = width
+        ));
+        for domain in &domains {
+            text.push_str(&format!(
+                "{:<width$}  {:>9}  {:>7}  {:>5}\n",
+                domain.domain,
+                domain.citations,
+                domain.sources,
+                domain.files,
+                width = width
+            ));
+        }
+    }
+    text
+}
+
+/// Totals, per-file counts and the most cited domains
+pub fn json(stats: &[FileStats], top: usize) -> String {
+    let total = |count: fn(&FileStats) -> usize| stats.iter().map(count).sum::<usize>();
+    let files: Vec<_> = stats
+        .iter()
+        .map(|s| {
+            json!({
+                "file": s.file,
+                "citations": s.citations,
+                "unresolved": s.unresolved,
+                "sources": s.sources,
+                "unused": s.unused,
+            })
+        })
+        .collect();
+    let domains: Vec<_> = top_domains(stats, top)
+        .into_iter()
+        .map(|d| {
+            json!({
+                "domain": d.domain,
+                "citations": d.citations,
+                "sources": d.sources,
+                "files": d.files,
+            })
+        })
+        .collect();
+    let report = json!({
+        "files": stats.len(),
+        "citations": total(|s| s.citations),
+        "unresolved": total(|s| s.unresolved),
+        "sources": total(|s| s.sources),
+        "unused": total(|s| s.unused),
+        "domains": domains,
+        "per_file": files,
+    });
+    format!("{:#}\n", report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use markdown_ai_cite_remove::extract_citations;
+
+    fn stats() -> Vec<FileStats> {
+        [
+            (
+                "a.md",
+                "A[1][2][1].\n\n[1]: https://www.reuters.com/x\n[2]: https://b.org",
+            ),
+            (
+                "b.md",
+                "B[1][3].\n\n[1]: https://Reuters.com/y\n[2]: https://c.org",
+            ),
+            ("c.md", "No citations."),
+        ]
+        .iter()
+        .map(|(name, text)| FileStats::new(name, &extract_citations(text)))
+        .collect()
+    }
+
+    #[test]
+    fn test_file_stats() {
+        let stats = stats();
+        assert_eq!(
+            (
+                stats[1].citations,
+                stats[1].unresolved,
+                stats[1].sources,
+                stats[1].unused
+            ),
+            (2, 1, 2, 1)
+        );
+        assert_eq!(
+            stats[0].domains,
+            [("reuters.com".to_string(), 2), ("b.org".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_domains_are_counted_across_files() {
+        let domains = domains(&stats());
+        let rows: Vec<_> = domains
+            .iter()
+            .map(|d| (d.domain.as_str(), d.citations, d.sources, d.files))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("reuters.com", 3, 2, 2),
+                ("b.org", 1, 1, 1),
+                ("c.org", 0, 1, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_human_and_json_reports() {
+        let text = human(&stats(), 1);
+        assert!(text.starts_with("Files:              3 (2 with citations)\n"));
+        assert!(text.contains("Inline citations:   5 (1 unresolved)\n"));
+        assert!(text.ends_with("reuters.com          3        2      2\n"));
+        assert!(!text.contains("b.org"));
+
+        let value: serde_json::Value = serde_json::from_str(&json(&stats(), 0)).unwrap();
+        assert_eq!(value["sources"], 4);
+        assert_eq!(value["domains"].as_array().unwrap().len(), 3);
+        assert_eq!(value["per_file"][2]["file"], "c.md");
+    }
+}
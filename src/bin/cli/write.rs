@@ -0,0 +1,243 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use filetime::FileTime;
+
+/// How `--in-place` replaces a file
+#[derive(Default)]
+pub struct InPlace {
+    /// Keep the original as `<file><suffix>`
+    pub backup_suffix: Option<String>,
+    /// Keep the permissions and modification time of the original
+    pub preserve: bool,
+    /// Edit the file a symbolic link points to instead of refusing
+    pub follow_symlinks: bool,
+}
+
+impl InPlace {
+    /// Replace the contents of `path` with `text`
+    ///
+    /// The original stays untouched until the new contents are on disk, and is
+    /// then swapped out in a single rename.
+    pub fn replace(&self, path: &Path, text: &str) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        let metadata = fs::metadata(&path)?;
+
+        let mtime = FileTime::from_last_modification_time(&metadata);
+
+        let temp = TempFile::create(&path)?;
+        temp.write(text)?;
+        if self.preserve {
+            fs::set_permissions(&temp.path, metadata.permissions())?;
+            filetime::set_file_mtime(&temp.path, mtime)?;
+        }
+        // The backup is the original, down to its permissions and mtime
+        if let Some(suffix) = &self.backup_suffix {
+            let backup = backup_path(&path, suffix);
+            fs::copy(&path, &backup)?;
+            fs::set_permissions(&backup, metadata.permissions())?;
+            filetime::set_file_mtime(&backup, mtime)?;
+        }
+        temp.persist(&path)
+    }
+
+    /// Fail early if `path` couldn't be replaced, before any work is done on it
+    pub fn check(&self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        fs::metadata(&path)?;
+        check_writable(&path)
+    }
+
+    /// The file to edit for `path`: the path itself, or the target of a
+    /// symbolic link if links are followed
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        let is_symlink = fs::symlink_metadata(path)?.file_type().is_symlink();
+        if !is_symlink {
+            Ok(path.to_path_buf())
+        } else if self.follow_symlinks {
+            fs::canonicalize(path)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "is a symbolic link (use --follow-symlinks to edit its target)",
+            ))
+        }
+    }
+}
+
+/// Fail early if `path` couldn't be written through a temporary file next to it
+pub fn check_writable(path: &Path) -> io::Result<()> {
+    TempFile::create(path).map(drop)
+}
+
+/// `file.md.bak` for `file.md` and `.bak`
+fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Write `text` to `path` through a temporary file, so that readers never see
+/// a partly written file
+pub fn write_atomic(path: &Path, text: &str) -> io::Result<()> {
+    let temp = TempFile::create(path)?;
+    temp.write(text)?;
+    temp.persist(path)
+}
+
+/// A file next to its destination, removed unless persisted
+struct TempFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl TempFile {
+    fn create(dest: &Path) -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = dest.file_name().unwrap_or_default().to_string_lossy();
+        loop {
+            let path = dest.with_file_name(format!(
+                ".{}.mdcr-{}-{}.tmp",
+                name,
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok(Self { path, file }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn write(&self, text: &str) -> io::Result<()> {
+        let mut file = &self.file;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()
+    }
+
+    fn persist(self, dest: &Path) -> io::Result<()> {
+        fs::rename(&self.path, dest)?;
+        std::mem::forget(self);
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mdcr-write-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_replace_keeps_a_backup_and_no_temp_files() {
+        let dir = scratch("backup");
+        let path = dir.join("a.md");
+        fs::write(&path, "Fact[1].").unwrap();
+        let old = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(&path, old).unwrap();
+        let permissions = fs::metadata(&path).unwrap().permissions();
+
+        let in_place = InPlace {
+            backup_suffix: Some(".bak".into()),
+            ..Default::default()
+        };
+        in_place.replace(&path, "Fact.").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Fact.");
+        let backup = dir.join("a.md.bak");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "Fact[1].");
+        let metadata = fs::metadata(&backup).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&metadata), old);
+        assert_eq!(metadata.permissions(), permissions);
+        assert_eq!(entries(&dir), ["a.md", "a.md.bak"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_preserve_keeps_the_modification_time() {
+        let dir = scratch("preserve");
+        let path = dir.join("a.md");
+        fs::write(&path, "Fact[1].").unwrap();
+        let old = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(&path, old).unwrap();
+
+        let in_place = InPlace {
+            preserve: true,
+            ..Default::default()
+        };
+        in_place.replace(&path, "Fact.").unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&metadata), old);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_refused_unless_followed() {
+        let dir = scratch("symlink");
+        let target = dir.join("target.md");
+        let link = dir.join("link.md");
+        fs::write(&target, "Fact[1].").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let error = InPlace::default().replace(&link, "Fact.").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let error = InPlace::default().check(&link).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Fact[1].");
+
+        let follow = InPlace {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        follow.check(&link).unwrap();
+        follow.replace(&link, "Fact.").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Fact.");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_writes_leave_nothing_behind() {
+        let dir = scratch("failure");
+        // The destination is a directory, so the rename fails
+        fs::create_dir_all(dir.join("a.md")).unwrap();
+        fs::write(dir.join("a.md").join("keep"), "").unwrap();
+        assert!(write_atomic(&dir.join("a.md"), "Fact.").is_err());
+        assert_eq!(entries(&dir), ["a.md"]);
+
+        // Checks leave nothing behind either
+        check_writable(&dir.join("b.md")).unwrap();
+        assert!(check_writable(&dir.join("missing").join("b.md")).is_err());
+        assert_eq!(entries(&dir), ["a.md"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+use std::ops::Range;
+
+use crate::patterns::Patterns;
+
+/// Kind of a reference block found by [`scan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BlockKind {
+    /// Footnote definition: `[^1]: text`, with indented paragraphs and lazy continuation
+    Footnote,
+    /// Link reference definition: `[1]: url "title"` or `[1] url`, title may sit on the next line
+    LinkDefinition,
+    /// Markdown link line: `[1](https://...)`
+    LinkLine,
+    /// Bibliographic entry: `[1] Author, A. (2024). Title.`
+    Entry,
+    /// Definition matched by a user-supplied pattern, with hanging indents
+    Custom,
+    /// Reference section header (`## References`) together with its body
+    Section,
+}
+
+/// A block of reference material spanning one or more whole lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Block {
+    pub kind: BlockKind,
+    /// Byte range in the scanned text, including the trailing newline
+    pub range: Range<usize>,
+    /// Zero-based line range covered by the block
+    pub lines: Range<usize>,
+}
+
+/// A single line of the scanned text
+struct Line<'a> {
+    /// Line content without the line terminator
+    text: &'a str,
+    start: usize,
+    /// Offset just past the line terminator
+    next: usize,
+    in_fence: bool,
+}
+
+impl Line<'_> {
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    fn is_indented(&self) -> bool {
+        self.text.starts_with('\t') || self.text.starts_with("    ")
+    }
+}
+
+/// Scan `text` for reference definitions and reference sections
+///
+/// Definitions are parsed as CommonMark-style blocks, so continuation lines are
+/// part of the block they belong to:
+/// - Footnote definitions continue over indented lines, indented paragraphs
+///   after blank lines, and lazy continuation lines
+/// - Link reference definitions may put the destination or title on the next line
+/// - Markdown link lines and bibliographic entries continue over hanging indents
+///
+/// Section blocks run from a reference header to the next heading of the same or
+/// higher level. Definitions inside a section are reported as well, so blocks may
+/// overlap. Lines inside fenced code blocks are never treated as reference material.
+///
+/// Returned blocks are sorted by start offset.
+pub(crate) fn scan(text: &str, patterns: &Patterns) -> Vec<Block> {
+    let lines = split_lines(text);
+    let mut blocks = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        match definition_kind(&lines[i], patterns) {
+            Some(kind) => {
+                let last = definition_end(&lines, i, kind, patterns);
+                blocks.push(block(&lines, kind, i, last));
+                i = last + 1;
+            }
+            None => i += 1,
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.in_fence || !patterns.is_reference_header(line.text) {
+            continue;
+        }
+        let level = heading_level(line.text).unwrap_or(1);
+        let last = lines[i + 1..]
+            .iter()
+            .position(|l| !l.in_fence && (ends_section(l.text, level)))
+            .map_or(lines.len() - 1, |offset| i + offset);
+        blocks.push(block(&lines, BlockKind::Section, i, last));
+    }
+
+    blocks.sort_by_key(|b| (b.range.start, std::cmp::Reverse(b.range.end)));
+    blocks
+}
+
+fn block(lines: &[Line], kind: BlockKind, first: usize, last: usize) -> Block {
+    Block {
+        kind,
+        range: lines[first].start..lines[last].next,
+        lines: first..last + 1,
+    }
+}
+
+fn split_lines(text: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    let mut fence: Option<&str> = None;
+
+    for raw in text.split_inclusive('\n') {
+        let content = raw.trim_end_matches('\n').trim_end_matches('\r');
+        let marker = fence_marker(content);
+        let in_fence = fence.is_some() || marker.is_some();
+
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if m.starts_with(open) && content.trim().len() == m.len() => {
+                fence = None
+            }
+            _ => {}
+        }
+
+        lines.push(Line {
+            text: content,
+            start: offset,
+            next: offset + raw.len(),
+            in_fence,
+        });
+        offset += raw.len();
+    }
+
+    lines
+}
+
+/// Return the fence run (e.g. ```` ``` ```` or `~~~~`) that opens or closes a code block
+fn fence_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let ch = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let run = trimmed.len() - trimmed.trim_start_matches(ch).len();
+    (run >= 3).then(|| &trimmed[..run])
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[hashes..];
+    ((1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then_some(hashes)
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|m| compact.chars().all(|c| c == *m))
+}
+
+fn ends_section(line: &str, level: usize) -> bool {
+    heading_level(line).is_some_and(|l| l <= level) || is_thematic_break(line)
+}
+
+/// Classify a line that starts a reference definition or entry
+fn definition_kind(line: &Line, patterns: &Patterns) -> Option<BlockKind> {
+    if line.in_fence {
+        return None;
+    }
+    let text = line.text;
+    if patterns.reference_definitions.is_match(text) {
+        let close = text.find(']')?;
+        let rest = &text[close + 1..];
+        let kind = match rest.chars().next() {
+            Some(':') if text.starts_with("[^") => BlockKind::Footnote,
+            Some(':') => BlockKind::LinkDefinition,
+            Some('(') => BlockKind::LinkLine,
+            // `[1] url` is a link definition, `[1] Author...` a bibliographic entry
+            _ if rest.trim_start().starts_with(['<', 'h']) && rest.contains("://") => {
+                BlockKind::LinkDefinition
+            }
+            _ => BlockKind::Entry,
+        };
+        Some(kind)
+    } else if patterns.reference_entry.is_match(text) {
+        Some(BlockKind::Entry)
+    } else if patterns
+        .custom_definitions
+        .as_ref()
+        .is_some_and(|custom| custom.is_match(text))
+    {
+        Some(BlockKind::Custom)
+    } else {
+        None
+    }
+}
+
+/// Find the index of the last line belonging to the definition starting at `first`
+fn definition_end(lines: &[Line], first: usize, kind: BlockKind, patterns: &Patterns) -> usize {
+    match kind {
+        BlockKind::Footnote => footnote_end(lines, first, patterns),
+        BlockKind::LinkDefinition => link_definition_end(lines, first),
+        _ => hanging_indent_end(lines, first),
+    }
+}
+
+fn footnote_end(lines: &[Line], first: usize, patterns: &Patterns) -> usize {
+    let mut last = first;
+    let mut j = first + 1;
+
+    while j < lines.len() {
+        let line = &lines[j];
+        if line.is_blank() {
+            // A blank line only continues the footnote if an indented paragraph follows
+            match lines[j..].iter().position(|l| !l.is_blank()) {
+                Some(offset) if lines[j + offset].is_indented() => j += offset,
+                _ => break,
+            }
+        } else if line.is_indented()
+            || (!lines[j - 1].is_blank() && !interrupts_paragraph(line, patterns))
+        {
+            last = j;
+            j += 1;
+        } else {
+            break;
+        }
+    }
+
+    last
+}
+
+fn link_definition_end(lines: &[Line], first: usize) -> usize {
+    let text = lines[first].text;
+    let mut last = first;
+
+    // `[1]:` with the destination on the following line
+    let destination_pending = text
+        .find("]:")
+        .is_some_and(|colon| text[colon + 2..].trim().is_empty());
+    if destination_pending && lines.get(last + 1).is_some_and(|l| !l.is_blank()) {
+        last += 1;
+    }
+
+    if lines.get(last + 1).is_some_and(|l| is_title_line(l.text)) {
+        last += 1;
+    }
+
+    last
+}
+
+fn hanging_indent_end(lines: &[Line], first: usize) -> usize {
+    let mut last = first;
+    while lines
+        .get(last + 1)
+        .is_some_and(|l| !l.is_blank() && l.text.starts_with([' ', '\t']))
+    {
+        last += 1;
+    }
+    last
+}
+
+/// A link title on its own line: `"Title"`, `'Title'` or `(Title)`
+fn is_title_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    [('"', '"'), ('\'', '\''), ('(', ')')]
+        .iter()
+        .any(|&(open, close)| {
+            trimmed.len() >= 2 && trimmed.starts_with(open) && trimmed.ends_with(close)
+        })
+}
+
+/// Whether `line` starts a new block instead of lazily continuing a paragraph
+fn interrupts_paragraph(line: &Line, patterns: &Patterns) -> bool {
+    let text = line.text.trim_start();
+    heading_level(text).is_some()
+        || is_thematic_break(text)
+        || fence_marker(line.text).is_some()
+        || text.starts_with(['>', '<', '|'])
+        || text.starts_with("- ")
+        || text.starts_with("* ")
+        || text.starts_with("+ ")
+        || text
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        || definition_kind(line, patterns).is_some()
+}
+
+/// Fenced code blocks, inline code spans and HTML comments in `text`, sorted
+pub(crate) fn protected_ranges(text: &str) -> Vec<Range<usize>> {
+    let lines = split_lines(text);
+    let mut ranges = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let in_fence = lines[i].in_fence;
+        let start = lines[i].start;
+        while i < lines.len() && lines[i].in_fence == in_fence {
+            i += 1;
+        }
+        let end = lines[i - 1].next;
+        if in_fence {
+            ranges.push(start..end);
+        } else {
+            inline_protected_ranges(text, start..end, &mut ranges);
+        }
+    }
+    ranges
+}
+
+/// Code spans and HTML comments within `within`, which holds no fences
+fn inline_protected_ranges(text: &str, within: Range<usize>, ranges: &mut Vec<Range<usize>>) {
+    let backticks = |at: usize| {
+        let rest = &text[at..within.end];
+        rest.len() - rest.trim_start_matches('`').len()
+    };
+
+    let mut pos = within.start;
+    while let Some(i) = text[pos..within.end].find(['`', '<']) {
+        let start = pos + i;
+        if text[start..within.end].starts_with("<!--") {
+            let end = text[start..within.end]
+                .find("-->")
+                .map_or(within.end, |j| start + j + 3);
+            ranges.push(start..end);
+            pos = end;
+        } else if text[start..].starts_with('`') {
+            // A code span closes at the next backtick run of the same length
+            let run = backticks(start);
+            let mut search = start + run;
+            let mut end = None;
+            while let Some(j) = text[search..within.end].find('`') {
+                let close = search + j;
+                let len = backticks(close);
+                if len == run {
+                    end = Some(close + len);
+                    break;
+                }
+                search = close + len;
+            }
+            match end {
+                Some(end) => {
+                    ranges.push(start..end);
+                    pos = end;
+                }
+                None => pos = start + run,
+            }
+        } else {
+            pos = start + 1;
+        }
+    }
+}
+
+/// Merge block ranges, joining ranges separated only by whitespace
+pub(crate) fn merge_ranges(
+    text: &str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+) -> Vec<Range<usize>> {
+    let mut ranges: Vec<_> = ranges.into_iter().collect();
+    ranges.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(prev)
+                if range.start <= prev.end || text[prev.end..range.start].trim().is_empty() =>
+            {
+                prev.end = prev.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<(BlockKind, &str)> {
+        scan(text, Patterns::get())
+            .into_iter()
+            .map(|b| (b.kind, &text[b.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_protected_ranges() {
+        let text = "Use `a[1]` or ``b`[2]``.\n<!-- [3] -->\n\n```\nx[4]\n```\nDone `open[5]";
+        let protected: Vec<_> = protected_ranges(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(
+            protected,
+            vec!["`a[1]`", "``b`[2]``", "<!-- [3] -->", "```\nx[4]\n```\n"]
+        );
+    }
+
+    #[test]
+    fn test_footnote_indented_paragraphs() {
+        let text =
+            "Intro.\n\n[^1]: First line\n    continued here.\n\n    Second paragraph.\n\nOutro.";
+        assert_eq!(
+            kinds(text),
+            vec![(
+                BlockKind::Footnote,
+                "[^1]: First line\n    continued here.\n\n    Second paragraph.\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_footnote_lazy_continuation() {
+        let text = "[^note]: A note that\nwraps lazily.\n\nBody text.";
+        assert_eq!(
+            kinds(text),
+            vec![(BlockKind::Footnote, "[^note]: A note that\nwraps lazily.\n")]
+        );
+    }
+
+    #[test]
+    fn test_footnote_interrupted_by_block_start() {
+        let text = "[^1]: Note\n## Heading\n- item";
+        assert_eq!(kinds(text), vec![(BlockKind::Footnote, "[^1]: Note\n")]);
+    }
+
+    #[test]
+    fn test_link_definition_title_on_next_line() {
+        let text = "[1]: https://example.com\n  \"Example\"\nParagraph.";
+        assert_eq!(
+            kinds(text),
+            vec![(
+                BlockKind::LinkDefinition,
+                "[1]: https://example.com\n  \"Example\"\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_link_definition_destination_on_next_line() {
+        let text = "[1]:\n  https://example.com\n  'Example'\nParagraph.";
+        assert_eq!(
+            kinds(text),
+            vec![(
+                BlockKind::LinkDefinition,
+                "[1]:\n  https://example.com\n  'Example'\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_entry_hanging_indent() {
+        let text = "[1] Smith, J. (2024). Title.\n    Journal of Things.\nNext.";
+        assert_eq!(
+            kinds(text),
+            vec![(
+                BlockKind::Entry,
+                "[1] Smith, J. (2024). Title.\n    Journal of Things.\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_section_runs_to_next_heading() {
+        let text = "Text.\n\n## Sources\n- [A](https://a.com)\n\n## Next\nMore.";
+        assert_eq!(
+            kinds(text),
+            vec![(BlockKind::Section, "## Sources\n- [A](https://a.com)\n\n")]
+        );
+    }
+
+    #[test]
+    fn test_fenced_code_ignored() {
+        let text = "```\n[1]: https://example.com\n## References\n```\n";
+        assert!(kinds(text).is_empty());
+    }
+
+    #[test]
+    fn test_merge_ranges_across_blank_lines() {
+        let text = "a\n\nb\nc";
+        assert_eq!(merge_ranges(text, [0..2, 3..5]), vec![0..5]);
+        assert_eq!(merge_ranges(text, [0..2, 5..6]), vec![0..2, 5..6]);
+    }
+}
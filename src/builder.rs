@@ -0,0 +1,143 @@
+use crate::config::RemoverConfig;
+use crate::error::Result;
+use crate::patterns::{CustomPatterns, Pattern, Patterns};
+use crate::pipeline::Pipeline;
+use crate::remover::CitationRemover;
+
+/// Builds a [`CitationRemover`] with extra citation patterns or a custom
+/// [`Pipeline`]
+///
+/// Custom patterns are added to the built-in ones, which still take
+/// precedence where both match. Without custom patterns the remover shares
+/// the precompiled built-in patterns, exactly like
+/// [`CitationRemover::with_config`].
+#[derive(Debug, Clone, Default)]
+pub struct CitationRemoverBuilder {
+    config: RemoverConfig,
+    custom: CustomPatterns,
+    pipeline: Option<Pipeline>,
+}
+
+impl CitationRemoverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `config` instead of the default configuration
+    pub fn config(mut self, config: RemoverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Remove inline markers matching `pattern`
+    ///
+    /// A named group `id` in a regex becomes the marker's identifier, e.g.
+    /// `\{\{src:(?P<id>\d+)\}\}`; otherwise the whole match is used.
+    pub fn inline_pattern(mut self, pattern: Pattern) -> Self {
+        self.custom.inline.push(pattern);
+        self
+    }
+
+    /// Remove definitions whose first line starts with a match of `pattern`
+    ///
+    /// Indented lines below are removed with the definition.
+    pub fn definition_pattern(mut self, pattern: Pattern) -> Self {
+        self.custom.definitions.push(pattern);
+        self
+    }
+
+    /// Remove sections under headings whose text matches `pattern` in full
+    pub fn header_pattern(mut self, pattern: Pattern) -> Self {
+        self.custom.headers.push(pattern);
+        self
+    }
+
+    /// Run `pipeline` instead of the stages the configuration enables
+    ///
+    /// The `remove_*` switches of the configuration are ignored; the
+    /// pipeline decides which stages run and in what order.
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Compile the patterns and build the remover
+    ///
+    /// Fails with [`RemoverError::InvalidPattern`](crate::RemoverError::InvalidPattern)
+    /// if a pattern is not a valid regex, matches the empty string, or
+    /// redefines a built-in capture group.
+    pub fn build(self) -> Result<CitationRemover> {
+        let patterns = if self.custom.is_empty() {
+            None
+        } else {
+            Some(
+                Patterns::with_vocabulary(
+                    &self.config.named_prefixes,
+                    &self.config.header_keywords,
+                )
+                .with_custom(&self.custom)?,
+            )
+        };
+        Ok(CitationRemover::with_parts(
+            self.config,
+            patterns,
+            self.pipeline,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RemoverError, RuleId};
+
+    #[test]
+    fn test_custom_patterns_are_removed_and_reported() {
+        let remover = CitationRemover::builder()
+            .inline_pattern(Pattern::regex(r"\{\{src:(?P<id>\d+)\}\}"))
+            .definition_pattern(Pattern::regex(r"src \d+ ->"))
+            .header_pattern(Pattern::literal("Quellen"))
+            .build()
+            .unwrap();
+
+        let input =
+            "Fact{{src:1}} here[2].\n\nsrc 1 -> https://a.com\n  continued\n\n## Quellen\n- Buch\n";
+        let (output, report) = remover.remove_with_report(input);
+        assert_eq!(output, "Fact here.\n");
+
+        let rules: Vec<_> = report.removals.iter().map(|r| r.rule.clone()).collect();
+        assert!(rules.contains(&RuleId::InlineCustom));
+        assert!(rules.contains(&RuleId::CustomDefinition));
+        assert!(rules.contains(&RuleId::ReferenceSection));
+
+        let analysis = remover.analyze(input);
+        assert_eq!(analysis.citations[0].id, "1");
+    }
+
+    #[test]
+    fn test_patterns_with_the_same_group_names() {
+        let remover = CitationRemover::builder()
+            .inline_pattern(Pattern::regex(r"\{\{src:(?P<id>\w+)\}\}"))
+            .inline_pattern(Pattern::regex(r"<<cit:(?P<id>\w+)>>"))
+            .build()
+            .unwrap();
+
+        let input = "One{{src:a}} two<<cit:b>>.";
+        assert_eq!(remover.remove(input), "One two.");
+        let ids: Vec<_> = remover
+            .analyze(input)
+            .citations
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        let result = CitationRemover::builder()
+            .inline_pattern(Pattern::regex("[unclosed"))
+            .build();
+        assert!(matches!(result, Err(RemoverError::InvalidPattern(_))));
+    }
+}
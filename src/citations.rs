@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use regex::Captures;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::{Block, BlockKind};
+use crate::patterns::Patterns;
+
+/// Syntax of an inline citation marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum CitationKind {
+    /// `[1]`, `［1］`
+    Numeric,
+    /// `[^1]`, `[^1_1]`
+    Footnote,
+    /// `[source:1]`, `[ref:2]`
+    Named,
+    /// `【1†source】`
+    Lenticular,
+    /// Matched by a user-supplied pattern
+    Custom,
+}
+
+impl CitationKind {
+    /// Kind of a marker matched by the built-in inline citation pattern
+    pub(crate) fn of_marker(marker: &str) -> Self {
+        if marker.starts_with('【') {
+            Self::Lenticular
+        } else if marker.starts_with("[^") {
+            Self::Footnote
+        } else if marker.contains(':') {
+            Self::Named
+        } else {
+            Self::Numeric
+        }
+    }
+}
+
+/// An inline citation marker found in the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Marker {
+    pub kind: CitationKind,
+    /// Identifier inside the marker (`1`, `1_1`, `note`)
+    pub id: String,
+    /// Named-citation prefix (`source`, `ref`), if any
+    pub prefix: Option<String>,
+    pub range: Range<usize>,
+    /// Position of the identifier in the text
+    pub id_range: Range<usize>,
+}
+
+impl Marker {
+    fn from_captures(caps: &Captures, custom: bool) -> Self {
+        let range = caps.get(0).map_or(0..0, |m| m.range());
+        let (kind, id) = if custom {
+            (
+                CitationKind::Custom,
+                caps.name("id").or_else(|| caps.get(0)),
+            )
+        } else if let Some(m) = caps.name("footnote") {
+            (CitationKind::Footnote, Some(m))
+        } else if let Some(m) = caps.name("numeric").or_else(|| caps.name("fullwidth")) {
+            (CitationKind::Numeric, Some(m))
+        } else if let Some(m) = caps.name("named") {
+            (CitationKind::Named, Some(m))
+        } else {
+            (CitationKind::Lenticular, caps.name("lenticular"))
+        };
+
+        Self {
+            kind,
+            id: id.map_or("", |m| m.as_str()).to_string(),
+            prefix: caps.name("prefix").map(|m| m.as_str().to_string()),
+            id_range: id.map_or(range.end..range.end, |m| m.range()),
+            range,
+        }
+    }
+
+    /// Definition labels this marker may refer to, most specific first
+    pub fn candidate_labels(&self) -> Vec<String> {
+        match &self.prefix {
+            Some(prefix) => vec![format!("{}:{}", prefix, self.id), self.id.clone()],
+            None => vec![self.id.clone()],
+        }
+    }
+}
+
+/// Find inline citation markers in `text`, skipping the given reference blocks
+/// and any marker that overlaps a protected range (code, HTML comments)
+///
+/// `protected` must be sorted by start, as returned by
+/// [`blocks::protected_ranges`](crate::blocks::protected_ranges).
+pub(crate) fn markers(
+    text: &str,
+    patterns: &Patterns,
+    skip: &[Block],
+    protected: &[Range<usize>],
+) -> Vec<Marker> {
+    patterns
+        .inline_matches(text)
+        .iter()
+        .map(|m| Marker::from_captures(&m.captures, m.custom))
+        .filter(|marker| {
+            !skip
+                .iter()
+                .any(|b| b.range.start <= marker.range.start && marker.range.end <= b.range.end)
+        })
+        .filter(|marker| {
+            let i = protected.partition_point(|r| r.end <= marker.range.start);
+            protected
+                .get(i)
+                .map_or(true, |r| r.start >= marker.range.end)
+        })
+        .collect()
+}
+
+/// A parsed reference definition, link line or bibliographic entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Definition {
+    pub kind: BlockKind,
+    /// Label without brackets or caret (`1`, `1_1`, `note`)
+    pub label: String,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// Definition body after the label, continuation lines joined by spaces
+    pub text: String,
+    pub range: Range<usize>,
+}
+
+impl Definition {
+    /// Parse a definition block; returns `None` for section blocks and
+    /// definitions matched by user-supplied patterns, which have no label
+    pub fn parse(text: &str, block: &Block) -> Option<Self> {
+        if matches!(block.kind, BlockKind::Section | BlockKind::Custom) {
+            return None;
+        }
+
+        let raw = &text[block.range.clone()];
+        let close = raw.find(']')?;
+        let label = raw[1..close].trim_start_matches('^').to_string();
+        let rest = raw[close + 1..]
+            .strip_prefix(':')
+            .unwrap_or(&raw[close + 1..]);
+        let body = rest
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let (url, title) = match block.kind {
+            BlockKind::LinkLine => {
+                let url = body.trim_start_matches('(').split(')').next().unwrap_or("");
+                (Some(url.to_string()), None)
+            }
+            BlockKind::LinkDefinition => {
+                split_destination(&body).unwrap_or_else(|| free_text(&body))
+            }
+            _ => free_text(&body),
+        };
+
+        Some(Self {
+            kind: block.kind,
+            label,
+            url: url.filter(|u| !u.is_empty()),
+            title: title.filter(|t| !t.is_empty()),
+            text: body,
+            range: block.range.clone(),
+        })
+    }
+
+    /// Whether the body is nothing but the URL
+    pub fn is_bare_url(&self) -> bool {
+        self.url.as_deref().is_some_and(|url| {
+            let body = self.text.trim_start_matches('<').trim_end_matches('>');
+            body == url
+        })
+    }
+}
+
+/// Parse all definition blocks in `text`
+pub(crate) fn definitions(text: &str, blocks: &[Block]) -> Vec<Definition> {
+    blocks
+        .iter()
+        .filter_map(|block| Definition::parse(text, block))
+        .collect()
+}
+
+/// Resolves inline markers to the definitions they refer to
+pub(crate) struct Resolver<'a> {
+    by_label: HashMap<&'a str, usize>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Index definitions by label; the first definition of a label wins
+    pub fn new(definitions: &'a [Definition]) -> Self {
+        let mut by_label = HashMap::new();
+        for (i, definition) in definitions.iter().enumerate() {
+            by_label.entry(definition.label.as_str()).or_insert(i);
+        }
+        Self { by_label }
+    }
+
+    /// Index of the definition `marker` refers to
+    pub fn resolve(&self, marker: &Marker) -> Option<usize> {
+        marker
+            .candidate_labels()
+            .iter()
+            .find_map(|label| self.by_label.get(label.as_str()).copied())
+    }
+}
+
+/// Split a link reference destination and optional title: `<url> "Title"`
+///
+/// Returns `None` if the body doesn't start with a destination, as in
+/// `[2]: Some book, 2020`.
+fn split_destination(body: &str) -> Option<(Option<String>, Option<String>)> {
+    let body = body.trim();
+    let (url, rest) = match body.strip_prefix('<') {
+        Some(inner) => inner.split_once('>').unwrap_or((inner, "")),
+        None => {
+            let (url, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+            if !is_destination(url) {
+                return None;
+            }
+            (url, rest)
+        }
+    };
+    let rest = rest.trim();
+    let title = [('"', '"'), ('\'', '\''), ('(', ')')]
+        .iter()
+        .find_map(|&(open, close)| rest.strip_prefix(open)?.strip_suffix(close));
+
+    Some((Some(url.to_string()), title.map(str::to_string)))
+}
+
+/// Whether `word` looks like a URL or a path rather than prose
+fn is_destination(word: &str) -> bool {
+    word.contains("://")
+        || ["mailto:", "www.", "/", "./", "../", "#"]
+            .iter()
+            .any(|prefix| word.starts_with(prefix))
+}
+
+/// `(url, title)` of a body in free text: a single `[title](url)` link, or
+/// the first URL in it
+fn free_text(body: &str) -> (Option<String>, Option<String>) {
+    match markdown_link(body) {
+        Some((title, url)) => (Some(url), Some(title)),
+        None => (find_url(body), None),
+    }
+}
+
+/// Extract `(title, url)` from a body consisting of a single `[title](url)` link
+fn markdown_link(body: &str) -> Option<(String, String)> {
+    let inner = body.trim().strip_prefix('[')?;
+    let (title, rest) = inner.split_once("](")?;
+    let url = rest.strip_suffix(')')?;
+    (!url.contains(char::is_whitespace)).then(|| (title.to_string(), url.to_string()))
+}
+
+/// Find the first `http(s)://` URL in free text
+fn find_url(body: &str) -> Option<String> {
+    let start = body.find("http://").or_else(|| body.find("https://"))?;
+    let url = body[start..]
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '"')
+        .next()?
+        .trim_end_matches(['.', ',', ';', ')']);
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks;
+
+    fn parse(text: &str) -> Vec<Definition> {
+        definitions(text, &blocks::scan(text, Patterns::get()))
+    }
+
+    #[test]
+    fn test_markers_kinds_and_ids() {
+        let text = "A[1] B[^1_2] C[source:3] D【4†source】";
+        let found = markers(text, Patterns::get(), &[], &[]);
+        let summary: Vec<_> = found.iter().map(|m| (m.kind, m.id.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (CitationKind::Numeric, "1"),
+                (CitationKind::Footnote, "1_2"),
+                (CitationKind::Named, "3"),
+                (CitationKind::Lenticular, "4"),
+            ]
+        );
+        assert_eq!(found[2].candidate_labels(), vec!["source:3", "3"]);
+    }
+
+    #[test]
+    fn test_markers_skip_definition_blocks() {
+        let text = "Text[^1].\n\n[^1]: https://example.com";
+        let blocks = blocks::scan(text, Patterns::get());
+        let found = markers(text, Patterns::get(), &blocks, &[]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].range, 4..8);
+    }
+
+    #[test]
+    fn test_markers_skip_code() {
+        let text = "A[1] `m[7]` B[2]
+
+```
+arr[5]
+```
+";
+        let protected = blocks::protected_ranges(text);
+        let ids: Vec<_> = markers(text, Patterns::get(), &[], &protected)
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, ["1", "2"]);
+    }
+
+    #[test]
+    fn test_parse_link_definition_with_title() {
+        let defs = parse("[1]: https://example.com \"Example Domain\"");
+        assert_eq!(defs[0].label, "1");
+        assert_eq!(defs[0].url.as_deref(), Some("https://example.com"));
+        assert_eq!(defs[0].title.as_deref(), Some("Example Domain"));
+    }
+
+    #[test]
+    fn test_parse_link_definition_without_destination() {
+        let defs = parse("[1]: /docs/intro.md\n[2]: Some book, 2020\n[3]: See https://c.com.");
+        assert_eq!(defs[0].url.as_deref(), Some("/docs/intro.md"));
+        assert_eq!(defs[1].url, None);
+        assert_eq!(defs[1].text, "Some book, 2020");
+        assert_eq!(defs[2].url.as_deref(), Some("https://c.com"));
+    }
+
+    #[test]
+    fn test_parse_link_line_and_footnote() {
+        let defs = parse("[2](https://a.com/x)\n\n[^1_5]: https://b.com");
+        assert_eq!(defs[0].url.as_deref(), Some("https://a.com/x"));
+        assert_eq!(defs[1].label, "1_5");
+        assert!(defs[1].is_bare_url());
+    }
+
+    #[test]
+    fn test_parse_footnote_markdown_link_and_entry() {
+        let defs =
+            parse("[^a]: [Title](https://a.com)\n[3] Smith, J. (2024). Paper. https://b.com.");
+        assert_eq!(defs[0].title.as_deref(), Some("Title"));
+        assert_eq!(defs[0].url.as_deref(), Some("https://a.com"));
+        assert_eq!(defs[1].kind, BlockKind::Entry);
+        assert_eq!(defs[1].url.as_deref(), Some("https://b.com"));
+        assert!(!defs[1].is_bare_url());
+    }
+}
@@ -1,5 +1,24 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::artifacts::Artifact;
+use crate::language::Language;
+use crate::names::named_enum;
+use crate::patterns::NAMED_PREFIXES;
+use crate::provider::Provider;
+use crate::replacement::Replacement;
+
 /// Configuration options for citation removal
+///
+/// With the `serde` feature, missing fields deserialize to their defaults, and
+/// `provider`, `mode` and `languages` keys are accepted as well; see
+/// [`RemoverConfig::from_toml_str`].
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "crate::config_file::Profile")
+)]
 pub struct RemoverConfig {
     /// Remove inline citations like [1][2]
     pub remove_inline_citations: bool,
@@ -21,6 +40,18 @@ pub struct RemoverConfig {
 
     /// Trim trailing whitespace from lines
     pub trim_lines: bool,
+
+    /// What inline citations are replaced with
+    pub replacement: Replacement,
+
+    /// Prefixes of named citations, e.g. `source` in `[source:1]`
+    pub named_prefixes: Vec<String>,
+
+    /// Headings that start a reference section, e.g. `References` in `## References`
+    pub header_keywords: Vec<String>,
+
+    /// Vendor-specific leftovers to remove, none by default
+    pub artifacts: Vec<Artifact>,
 }
 
 impl Default for RemoverConfig {
@@ -33,6 +64,10 @@ impl Default for RemoverConfig {
             normalize_whitespace: true,
             remove_blank_lines: true,
             trim_lines: true,
+            replacement: Replacement::Delete,
+            named_prefixes: strings(NAMED_PREFIXES),
+            header_keywords: strings(Language::English.header_keywords()),
+            artifacts: Vec::new(),
         }
     }
 }
@@ -46,32 +81,174 @@ impl RemoverConfig {
     /// Create a configuration that only removes inline citations
     pub fn inline_only() -> Self {
         Self {
-            remove_inline_citations: true,
-            remove_reference_links: false,
-            remove_reference_headers: false,
-            remove_reference_entries: false,
-            normalize_whitespace: true,
             remove_blank_lines: false,
-            trim_lines: true,
+            ..Self::default().with_mode(RemovalMode::InlineOnly)
+        }
+    }
+
+    /// Configuration for documents from `provider`
+    ///
+    /// Everything the default configuration removes is still removed; the
+    /// profile adds the provider's named-citation prefixes and artifact
+    /// cleanups. Combine it with [`with_mode`](Self::with_mode) to narrow the
+    /// scope.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, Provider, RemovalMode, RemoverConfig};
+    ///
+    /// let config = RemoverConfig::for_provider(Provider::ChatGpt);
+    /// let remover = CitationRemover::with_config(config);
+    /// let output = remover.remove(
+    ///     "Rust is fast :contentReference[oaicite:0]{index=0}. See [docs](https://rust-lang.org/?utm_source=chatgpt.com).",
+    /// );
+    /// assert_eq!(output, "Rust is fast. See [docs](https://rust-lang.org/).");
+    ///
+    /// let config = RemoverConfig::for_provider(Provider::DeepSeek).with_mode(RemovalMode::InlineOnly);
+    /// let remover = CitationRemover::with_config(config);
+    /// assert_eq!(remover.remove("Fact[citation:3]."), "Fact.");
+    /// ```
+    pub fn for_provider(provider: Provider) -> Self {
+        Self::default().with_provider(provider)
+    }
+
+    /// Add the named-citation prefixes and artifact cleanups of `provider`
+    ///
+    /// Cleanups of inline markers are only added if inline citations are
+    /// removed, and cleanups of reference lists only if reference entries are.
+    pub fn with_provider(mut self, provider: Provider) -> Self {
+        for prefix in provider.named_prefixes() {
+            self = self.with_named_prefix(*prefix);
+        }
+        for &artifact in provider.artifacts() {
+            if self.covers(artifact) && !self.artifacts.contains(&artifact) {
+                self.artifacts.push(artifact);
+            }
+        }
+        self
+    }
+
+    /// Configuration for the provider [`detected`](Provider::detect) in
+    /// `markdown`, or the default configuration if none is likely
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, RemoverConfig};
+    ///
+    /// let input = "Fact[citation:1] and more[citation:2].";
+    /// let remover = CitationRemover::with_config(RemoverConfig::auto(input));
+    /// assert_eq!(remover.remove(input), "Fact and more.");
+    /// ```
+    pub fn auto(markdown: &str) -> Self {
+        Provider::detect(markdown).map_or_else(Self::default, Self::for_provider)
+    }
+
+    /// Limit removal to inline citations or reference sections
+    ///
+    /// Sets the `remove_*` switches and leaves the whitespace settings alone.
+    /// Artifact cleanups of the excluded kind are dropped, e.g. Copilot's source
+    /// list with [`InlineOnly`](RemovalMode::InlineOnly).
+    pub fn with_mode(mut self, mode: RemovalMode) -> Self {
+        let (inline, references) = match mode {
+            RemovalMode::All => (true, true),
+            RemovalMode::InlineOnly => (true, false),
+            RemovalMode::ReferencesOnly => (false, true),
+        };
+        self.remove_inline_citations = inline;
+        self.remove_reference_links = references;
+        self.remove_reference_headers = references;
+        self.remove_reference_entries = references;
+        let artifacts = std::mem::take(&mut self.artifacts);
+        self.artifacts = artifacts.into_iter().filter(|&a| self.covers(a)).collect();
+        self
+    }
+
+    /// Whether the `remove_*` switches allow the cleanup of `artifact`
+    fn covers(&self, artifact: Artifact) -> bool {
+        if artifact.is_inline() {
+            self.remove_inline_citations
+        } else if artifact.is_reference() {
+            self.remove_reference_entries
+        } else {
+            true
+        }
+    }
+
+    /// Also recognise named citations with `prefix`, e.g. `citation` for
+    /// DeepSeek's `[citation:3]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, RemoverConfig};
+    ///
+    /// let config = RemoverConfig::default().with_named_prefix("citation");
+    /// let remover = CitationRemover::with_config(config);
+    /// assert_eq!(remover.remove("Fact[citation:3]."), "Fact.");
+    /// ```
+    pub fn with_named_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        if !self.named_prefixes.contains(&prefix) {
+            self.named_prefixes.push(prefix);
+        }
+        self
+    }
+
+    /// Also recognise reference sections headed `keyword`, e.g. `Further Reading`
+    pub fn with_header_keyword(mut self, keyword: impl Into<String>) -> Self {
+        let keyword = keyword.into();
+        if !self.header_keywords.contains(&keyword) {
+            self.header_keywords.push(keyword);
+        }
+        self
+    }
+
+    /// Also recognise the reference headings of `language`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, Language, RemoverConfig};
+    ///
+    /// let config = RemoverConfig::default().with_language(Language::German);
+    /// let remover = CitationRemover::with_config(config);
+    /// let output = remover.remove("Fakt[1].\n\n## Quellen\n- https://example.de");
+    /// assert_eq!(output, "Fakt.");
+    /// ```
+    pub fn with_language(mut self, language: Language) -> Self {
+        for keyword in language.header_keywords() {
+            if !self.header_keywords.iter().any(|k| k == keyword) {
+                self.header_keywords.push(keyword.to_string());
+            }
         }
+        self
+    }
+
+    /// Whether the named prefixes and header keywords are the built-in ones
+    pub(crate) fn has_default_vocabulary(&self) -> bool {
+        self.named_prefixes == NAMED_PREFIXES
+            && self.header_keywords == Language::English.header_keywords()
     }
 
     /// Create a configuration that only removes reference sections
     pub fn references_only() -> Self {
-        Self {
-            remove_inline_citations: false,
-            remove_reference_links: true,
-            remove_reference_headers: true,
-            remove_reference_entries: true,
-            normalize_whitespace: true,
-            remove_blank_lines: true,
-            trim_lines: true,
-        }
+        Self::default().with_mode(RemovalMode::ReferencesOnly)
     }
 }
 
-/// Mode for handling different citation styles
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+/// Which kinds of citations to remove, set with [`RemoverConfig::with_mode`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum RemovalMode {
     /// Remove all citation types
     All,
@@ -80,3 +257,11 @@ pub enum RemovalMode {
     /// Remove only reference lists, keep inline citations
     ReferencesOnly,
 }
+
+named_enum! {
+    RemovalMode, "removal mode" {
+        All => "all",
+        InlineOnly => "inline-only",
+        ReferencesOnly => "references-only",
+    }
+}
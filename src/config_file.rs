@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::artifacts::Artifact;
+use crate::config::{RemovalMode, RemoverConfig};
+use crate::error::{RemoverError, Result};
+use crate::language::Language;
+use crate::provider::Provider;
+use crate::replacement::Replacement;
+
+/// Settings as written in a configuration file, every one optional
+///
+/// `provider` and `mode` are applied first, then the explicit settings, then
+/// `languages`. Only a [`ConfigFile`] may contain named `profiles`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    provider: Option<Provider>,
+    mode: Option<RemovalMode>,
+    remove_inline_citations: Option<bool>,
+    remove_reference_links: Option<bool>,
+    remove_reference_headers: Option<bool>,
+    remove_reference_entries: Option<bool>,
+    normalize_whitespace: Option<bool>,
+    remove_blank_lines: Option<bool>,
+    trim_lines: Option<bool>,
+    replacement: Option<Replacement>,
+    named_prefixes: Option<Vec<String>>,
+    header_keywords: Option<Vec<String>>,
+    artifacts: Option<Vec<Artifact>>,
+    #[serde(default)]
+    languages: Vec<Language>,
+    profiles: Option<BTreeMap<String, Profile>>,
+}
+
+impl Profile {
+    /// `config` with these settings on top
+    fn apply(&self, mut config: RemoverConfig) -> RemoverConfig {
+        if let Some(provider) = self.provider {
+            config = config.with_provider(provider);
+        }
+        if let Some(mode) = self.mode {
+            config = config.with_mode(mode);
+        }
+
+        let switches = [
+            (
+                self.remove_inline_citations,
+                &mut config.remove_inline_citations,
+            ),
+            (
+                self.remove_reference_links,
+                &mut config.remove_reference_links,
+            ),
+            (
+                self.remove_reference_headers,
+                &mut config.remove_reference_headers,
+            ),
+            (
+                self.remove_reference_entries,
+                &mut config.remove_reference_entries,
+            ),
+            (self.normalize_whitespace, &mut config.normalize_whitespace),
+            (self.remove_blank_lines, &mut config.remove_blank_lines),
+            (self.trim_lines, &mut config.trim_lines),
+        ];
+        for (value, switch) in switches {
+            if let Some(value) = value {
+                *switch = value;
+            }
+        }
+        if let Some(replacement) = &self.replacement {
+            config.replacement = replacement.clone();
+        }
+        if let Some(prefixes) = &self.named_prefixes {
+            config.named_prefixes = prefixes.clone();
+        }
+        if let Some(keywords) = &self.header_keywords {
+            config.header_keywords = keywords.clone();
+        }
+        if let Some(artifacts) = &self.artifacts {
+            config.artifacts = artifacts.clone();
+        }
+
+        self.languages
+            .iter()
+            .fold(config, |config, &language| config.with_language(language))
+    }
+}
+
+impl TryFrom<Profile> for RemoverConfig {
+    type Error = String;
+
+    fn try_from(profile: Profile) -> std::result::Result<Self, Self::Error> {
+        if profile.profiles.is_some() {
+            return Err(
+                "named profiles are only allowed at the top of a configuration file".into(),
+            );
+        }
+        Ok(profile.apply(RemoverConfig::default()))
+    }
+}
+
+impl RemoverConfig {
+    /// Parse a configuration from TOML
+    ///
+    /// Missing settings keep their defaults. Besides the fields of
+    /// `RemoverConfig`, `provider`, `mode` and `languages` apply a
+    /// [`Provider`] profile, a [`RemovalMode`] and [`Language`] packs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{Provider, RemovalMode, RemoverConfig};
+    ///
+    /// let config = RemoverConfig::from_toml_str(
+    ///     r#"
+    ///     provider = "deepseek"
+    ///     mode = "inline-only"
+    ///     replacement = { text = "†" }
+    ///     languages = ["german"]
+    ///     "#,
+    /// )?;
+    /// assert!(config.named_prefixes.contains(&"citation".to_string()));
+    /// assert!(!config.remove_reference_links);
+    /// assert!(config.header_keywords.contains(&"Quellen".to_string()));
+    /// # Ok::<(), markdown_ai_cite_remove::RemoverError>(())
+    /// ```
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        toml::from_str(toml).map_err(|e| RemoverError::Config(e.to_string()))
+    }
+
+    /// Parse a configuration from JSON, with the same keys as
+    /// [`from_toml_str`](Self::from_toml_str)
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| RemoverError::Config(e.to_string()))
+    }
+
+    /// Load a configuration from a `.json` file, or a TOML file otherwise
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_toml_str(&text)
+        }
+        .map_err(|e| in_file(path, e))
+    }
+
+    /// Serialize the configuration as TOML
+    ///
+    /// Fails for [`Replacement::Custom`], which cannot be serialized.
+    pub fn to_toml_string(&self) -> Result<String> {
+        toml::to_string(self).map_err(|e| RemoverError::Config(e.to_string()))
+    }
+}
+
+/// A configuration file with a base configuration and named profiles
+///
+/// ```toml
+/// # Used when no profile is selected, and as the base of every profile
+/// provider = "chatgpt"
+///
+/// [profiles.keep-sources]
+/// mode = "inline-only"
+///
+/// [profiles.placeholders]
+/// replacement = { text = "†" }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    base: Profile,
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl ConfigFile {
+    /// Name of the file [`find`](Self::find) looks for
+    pub const FILE_NAME: &'static str = ".mdcr.toml";
+
+    /// Parse a configuration file from TOML
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        Self::from_profile(toml::from_str(toml).map_err(|e| RemoverError::Config(e.to_string()))?)
+    }
+
+    /// Parse a configuration file from JSON
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        Self::from_profile(
+            serde_json::from_str(json).map_err(|e| RemoverError::Config(e.to_string()))?,
+        )
+    }
+
+    /// Load a `.json` file, or a TOML file otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json_str(&text)
+        } else {
+            Self::from_toml_str(&text)
+        }
+        .map_err(|e| in_file(path, e))
+    }
+
+    /// The nearest [`FILE_NAME`](Self::FILE_NAME) in `dir` or one of its ancestors
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use markdown_ai_cite_remove::ConfigFile;
+    ///
+    /// let cwd = std::env::current_dir()?;
+    /// let config = match ConfigFile::find(&cwd) {
+    ///     Some(path) => ConfigFile::load(path)?.config(Some("keep-sources"))?,
+    ///     None => Default::default(),
+    /// };
+    /// # Ok::<(), markdown_ai_cite_remove::RemoverError>(())
+    /// ```
+    pub fn find(dir: impl AsRef<Path>) -> Option<PathBuf> {
+        dir.as_ref()
+            .ancestors()
+            .map(|dir| dir.join(Self::FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    fn from_profile(mut base: Profile) -> Result<Self> {
+        let profiles = base.profiles.take().unwrap_or_default();
+        if let Some(name) = profiles
+            .iter()
+            .find_map(|(name, profile)| profile.profiles.as_ref().map(|_| name))
+        {
+            return Err(RemoverError::Config(format!(
+                "profile '{}' cannot contain profiles",
+                name
+            )));
+        }
+        Ok(Self { base, profiles })
+    }
+
+    /// Names of the profiles defined in the file, sorted
+    pub fn profile_names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// The base configuration, with the settings of `profile` on top
+    ///
+    /// Fails if `profile` is not defined in the file.
+    pub fn config(&self, profile: Option<&str>) -> Result<RemoverConfig> {
+        let config = self.base.apply(RemoverConfig::default());
+        let Some(name) = profile else {
+            return Ok(config);
+        };
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.apply(config)),
+            None => {
+                let names: Vec<_> = self.profile_names().collect();
+                Err(RemoverError::Config(if names.is_empty() {
+                    format!("unknown profile '{}' (no profiles are defined)", name)
+                } else {
+                    format!("unknown profile '{}' (expected {})", name, names.join(", "))
+                }))
+            }
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Name `path` in configuration errors
+fn in_file(path: &Path, error: RemoverError) -> RemoverError {
+    match error {
+        RemoverError::Config(message) => {
+            RemoverError::Config(format!("{}: {}", path.display(), message))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_apply_on_top_of_base() {
+        let file = ConfigFile::from_toml_str(
+            r#"
+            provider = "chatgpt"
+            trim_lines = false
+
+            [profiles.keep-sources]
+            mode = "inline-only"
+
+            [profiles.placeholders]
+            replacement = "html-comment"
+            artifacts = []
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            file.profile_names().collect::<Vec<_>>(),
+            ["keep-sources", "placeholders"]
+        );
+
+        let base = file.config(None).unwrap();
+        assert_eq!(base.artifacts, Provider::ChatGpt.artifacts());
+        assert!(!base.trim_lines);
+
+        let keep = file.config(Some("keep-sources")).unwrap();
+        assert!(!keep.remove_reference_links);
+        assert!(!keep.trim_lines);
+        assert_eq!(keep.artifacts, Provider::ChatGpt.artifacts());
+
+        let placeholders = file.config(Some("placeholders")).unwrap();
+        assert!(matches!(placeholders.replacement, Replacement::HtmlComment));
+        assert!(placeholders.artifacts.is_empty());
+
+        let error = file.config(Some("missing")).unwrap_err().to_string();
+        assert!(
+            error.contains("expected keep-sources, placeholders"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        for toml in [
+            "trim_line = false",
+            "mode = \"sometimes\"",
+            "[profiles.a.profiles.b]",
+            "replacement = \"custom\"",
+        ] {
+            assert!(
+                matches!(
+                    ConfigFile::from_toml_str(toml),
+                    Err(RemoverError::Config(_))
+                ),
+                "{}",
+                toml
+            );
+        }
+        assert!(RemoverConfig::from_toml_str("[profiles.a]").is_err());
+    }
+
+    #[test]
+    fn test_config_round_trips_through_toml_and_json() {
+        let config = RemoverConfig::for_provider(Provider::DeepSeek)
+            .with_mode(RemovalMode::InlineOnly)
+            .with_language(Language::French);
+        let config = RemoverConfig {
+            replacement: Replacement::Text("†".into()),
+            ..config
+        };
+
+        let toml = config.to_toml_string().unwrap();
+        let loaded = RemoverConfig::from_toml_str(&toml).unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", config));
+
+        let json = serde_json::to_string(&config).unwrap();
+        let loaded = RemoverConfig::from_json_str(&json).unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", config));
+
+        let custom = RemoverConfig {
+            replacement: Replacement::custom(|_: &crate::CitationMatch| String::new()),
+            ..Default::default()
+        };
+        assert!(custom.to_toml_string().is_err());
+    }
+}
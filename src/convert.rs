@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::{self, BlockKind};
+use crate::citations::{self, CitationKind, Definition, Marker, Resolver};
+use crate::edits::splice;
+use crate::names::named_enum;
+use crate::patterns::Patterns;
+
+/// Target format for [`CitationRemover::convert`](crate::CitationRemover::convert)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum CitationFormat {
+    /// CommonMark footnotes: markers become `[^1]`, and the reference block
+    /// becomes `[^1]: [Title](url)` definitions at the end of the document
+    Footnotes,
+    /// Inline Markdown links built from each marker's definition; the
+    /// reference block is dropped
+    InlineLinks(LinkStyle),
+}
+
+/// How [`CitationFormat::InlineLinks`] renders a resolved marker
+///
+/// Anchor text is the definition title, or the URL's domain when there is no title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum LinkStyle {
+    /// `grew 40% ([Reuters](https://...))`
+    #[default]
+    Parenthesized,
+    /// `grew [40%](https://...)` - links the word before the marker
+    LinkedWord,
+    /// `grew 40%<sup>[1](https://...)</sup>` - anchor text is the citation number
+    Superscript,
+}
+
+named_enum! {
+    LinkStyle, "link style" {
+        Parenthesized => "parenthesized",
+        LinkedWord => "linked-word",
+        Superscript => "superscript",
+    }
+}
+
+/// Result of rewriting a document's citations
+pub(crate) struct Converted {
+    /// Document text with markers rewritten and reference blocks removed
+    pub body: String,
+    /// Generated definitions to append after the body (may be empty)
+    pub appendix: String,
+}
+
+/// Rewrite inline markers into `format`, consuming the reference blocks
+///
+/// Markers are numbered from 1 in order of first appearance. Markers that do
+/// not resolve to a definition carry no source and are removed, as are markers
+/// converted to inline links whose definition has no URL.
+pub(crate) fn convert(text: &str, patterns: &Patterns, format: CitationFormat) -> Converted {
+    let blocks = blocks::scan(text, patterns);
+    let definitions = citations::definitions(text, &blocks);
+    let markers = citations::markers(text, patterns, &blocks, &blocks::protected_ranges(text));
+
+    let resolver = Resolver::new(&definitions);
+
+    let mut numbers: HashMap<&str, usize> = HashMap::new();
+    let mut cited: Vec<&Definition> = Vec::new();
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+
+    for marker in &markers {
+        let definition = resolver.resolve(marker).map(|i| &definitions[i]);
+
+        let Some(definition) = definition else {
+            edits.push((marker.range.clone(), String::new()));
+            continue;
+        };
+        let number = *numbers.entry(definition.label.as_str()).or_insert_with(|| {
+            cited.push(definition);
+            cited.len()
+        });
+
+        let edit = match (format, &definition.url) {
+            (CitationFormat::Footnotes, _) => (marker.range.clone(), format!("[^{}]", number)),
+            (CitationFormat::InlineLinks(style), Some(url)) => {
+                let previous_end = edits.last().map_or(0, |(range, _)| range.end);
+                let link = Link {
+                    anchor: anchor_text(definition, url),
+                    url,
+                    number,
+                };
+                inline_link(text, marker, &link, style, previous_end)
+            }
+            (CitationFormat::InlineLinks(_), None) => (marker.range.clone(), String::new()),
+        };
+        edits.push(edit);
+    }
+
+    let block_ranges = blocks.iter().map(|block| block.range.clone());
+    for range in blocks::merge_ranges(text, block_ranges) {
+        edits.push((range, String::new()));
+    }
+
+    let appendix = match format {
+        CitationFormat::Footnotes => cited
+            .iter()
+            .enumerate()
+            .map(|(i, definition)| format!("[^{}]: {}", i + 1, footnote_text(definition)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        CitationFormat::InlineLinks(_) => String::new(),
+    };
+
+    Converted {
+        body: splice(text, edits),
+        appendix,
+    }
+}
+
+/// A resolved link target for an inline marker
+struct Link<'a> {
+    anchor: String,
+    url: &'a str,
+    number: usize,
+}
+
+/// Build the edit that turns `marker` into an inline link
+///
+/// ChatGPT-style reference links (`[Reuters][3]`) keep their own text and
+/// become `[Reuters](url)` in every style.
+fn inline_link(
+    text: &str,
+    marker: &Marker,
+    link: &Link,
+    style: LinkStyle,
+    previous_end: usize,
+) -> (Range<usize>, String) {
+    let before = &text[previous_end..marker.range.start];
+
+    if marker.kind == CitationKind::Numeric {
+        if let Some(label) = reference_link_text(before) {
+            let start = marker.range.start - label.len() - 2;
+            return (
+                start..marker.range.end,
+                format!("[{}]({})", label, link.url),
+            );
+        }
+    }
+
+    let follows_marker = previous_end == marker.range.start && previous_end > 0;
+    match style {
+        LinkStyle::Superscript => (
+            marker.range.clone(),
+            format!("<sup>[{}]({})</sup>", link.number, link.url),
+        ),
+        LinkStyle::LinkedWord if !follows_marker => {
+            let word_start = before.rfind(char::is_whitespace).map_or(0, |i| {
+                i + before[i..].chars().next().map_or(1, char::len_utf8)
+            });
+            let word = &before[word_start..];
+            let trimmed = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"']);
+            let punctuation = &word[trimmed.len()..];
+            if trimmed.is_empty() {
+                parenthesized(text, marker, link)
+            } else {
+                let start = marker.range.start - word.len();
+                let replacement = format!("[{}]({}){}", trimmed, link.url, punctuation);
+                (start..marker.range.end, replacement)
+            }
+        }
+        _ => parenthesized(text, marker, link),
+    }
+}
+
+fn parenthesized(text: &str, marker: &Marker, link: &Link) -> (Range<usize>, String) {
+    let separator = match text[..marker.range.start].chars().next_back() {
+        Some(c) if !c.is_whitespace() => " ",
+        _ => "",
+    };
+    let replacement = format!("{}([{}]({}))", separator, link.anchor, link.url);
+    (marker.range.clone(), replacement)
+}
+
+/// Text of a `[label]` immediately before a marker, as in `[Reuters][3]`
+fn reference_link_text(before: &str) -> Option<&str> {
+    let inner = before.strip_suffix(']')?;
+    let open = inner.rfind('[')?;
+    let label = &inner[open + 1..];
+    (!label.is_empty() && !label.contains([']', '\n'])).then_some(label)
+}
+
+/// Anchor text for a definition: its title, or the URL's domain
+fn anchor_text(definition: &Definition, url: &str) -> String {
+    escape_brackets(&definition.title.clone().unwrap_or_else(|| domain(url)))
+}
+
+/// Escape square brackets so text can sit inside a link's `[...]`
+fn escape_brackets(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Host part of a URL without a leading `www.`
+pub(crate) fn domain(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(rest)
+        .rsplit('@')
+        .next()
+        .unwrap_or(rest);
+    host.trim_start_matches("www.").to_string()
+}
+
+/// Footnote body for a definition: `[Title](url)`, `<url>` or the original text
+pub(crate) fn footnote_text(definition: &Definition) -> String {
+    match (&definition.title, &definition.url) {
+        (Some(title), Some(url)) => format!("[{}]({})", escape_brackets(title), url),
+        (None, Some(url)) if definition.is_bare_url() || definition.kind == BlockKind::LinkLine => {
+            format!("<{}>", url)
+        }
+        _ => definition.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn footnotes(text: &str) -> Converted {
+        convert(text, Patterns::get(), CitationFormat::Footnotes)
+    }
+
+    fn links(text: &str, style: LinkStyle) -> String {
+        convert(text, Patterns::get(), CitationFormat::InlineLinks(style)).body
+    }
+
+    #[test]
+    fn test_footnotes_numbered_by_first_appearance() {
+        let text = "A[2] B[1] C[2].\n\n[1]: https://one.com \"One\"\n[2]: https://two.com";
+        let converted = footnotes(text);
+        assert_eq!(converted.body, "A[^1] B[^2] C[^1].\n\n");
+        assert_eq!(
+            converted.appendix,
+            "[^1]: <https://two.com>\n[^2]: [One](https://one.com)"
+        );
+    }
+
+    #[test]
+    fn test_footnotes_unresolved_markers_removed() {
+        let converted = footnotes("Claim【3†source】 and[9].");
+        assert_eq!(converted.body, "Claim and.");
+        assert!(converted.appendix.is_empty());
+    }
+
+    #[test]
+    fn test_footnotes_leave_code_alone() {
+        let text = "A[1] `m[7]`.\n\n```\narr[1]\n```\n\n[1]: https://a.com";
+        let converted = footnotes(text);
+        assert_eq!(converted.body, "A[^1] `m[7]`.\n\n```\narr[1]\n```\n\n");
+        assert_eq!(converted.appendix, "[^1]: <https://a.com>");
+    }
+
+    #[test]
+    fn test_footnotes_keep_entry_text() {
+        let text = "Fact[source:1].\n\n## References\n[1] Smith, J. (2024). Paper.";
+        let converted = footnotes(text);
+        assert_eq!(converted.body, "Fact[^1].\n\n");
+        assert_eq!(converted.appendix, "[^1]: Smith, J. (2024). Paper.");
+    }
+
+    #[test]
+    fn test_footnotes_escape_brackets_in_titles() {
+        let converted = footnotes("Fact[1].\n\n[1]: https://a.com \"A [b] c\"");
+        assert_eq!(converted.appendix, "[^1]: [A \\[b\\] c](https://a.com)");
+    }
+
+    #[test]
+    fn test_inline_links_parenthesized() {
+        let text = "Sales grew 40%[1][2].\n\n[1]: https://www.reuters.com/x \"Reuters\"\n[2]: https://ft.com/y";
+        assert_eq!(
+            links(text, LinkStyle::Parenthesized),
+            "Sales grew 40% ([Reuters](https://www.reuters.com/x)) ([ft.com](https://ft.com/y)).\n\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_links_linked_word() {
+        let text = "Sales grew 40%.[1]\n\n[1]: https://www.reuters.com/x";
+        assert_eq!(
+            links(text, LinkStyle::LinkedWord),
+            "Sales grew [40%](https://www.reuters.com/x).\n\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_links_superscript() {
+        let text = "A[2] B[1].\n\n[1]: https://a.com\n[2]: https://b.com";
+        assert_eq!(
+            links(text, LinkStyle::Superscript),
+            "A<sup>[1](https://b.com)</sup> B<sup>[2](https://a.com)</sup>.\n\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_links_reference_style_link() {
+        let text = "Guidelines. ([Apple Podcasts][4])\n\n[4]: https://apple.com/x \"Apple\"";
+        assert_eq!(
+            links(text, LinkStyle::Parenthesized),
+            "Guidelines. ([Apple Podcasts](https://apple.com/x))\n\n"
+        );
+    }
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(domain("https://www.example.com/path?q=1"), "example.com");
+        assert_eq!(domain("http://user@host.org:8080"), "host.org:8080");
+    }
+
+    #[test]
+    fn test_link_style_names_round_trip() {
+        for &style in LinkStyle::ALL {
+            assert_eq!(style.to_string().parse::<LinkStyle>(), Ok(style));
+        }
+        assert!("Linked-Word".parse::<LinkStyle>().is_ok());
+        assert!("footnote".parse::<LinkStyle>().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::artifacts::Artifact;
+use crate::provider::Provider;
+
+/// Lowest confidence at which [`Provider::detect`] picks a provider
+pub(crate) const AUTO_THRESHOLD: f64 = 0.5;
+
+/// A provider that may have produced a document
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Detection {
+    pub provider: Provider,
+    /// Between 0 and 1
+    pub confidence: f64,
+    /// Evidence found for this provider
+    pub signals: Vec<Signal>,
+}
+
+/// Evidence of where a document came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Signal {
+    /// `【1†source】`
+    LenticularMarker,
+    /// `:contentReference[oaicite:0]{index=0}`
+    ContentReference,
+    /// `citeturn0search0`
+    CiteTurn,
+    /// `utm_source=chatgpt.com` in a link
+    #[cfg_attr(feature = "serde", serde(rename = "chatgpt-tracking"))]
+    ChatGptTracking,
+    /// Many headings besides ChatGPT markers, as in Deep Research reports
+    LongReport,
+    /// `[^1_2]`
+    UnderscoreFootnote,
+    /// The Perplexity logo or `⁂` separators
+    PerplexityChrome,
+    /// `[1](https://…)` reference lines
+    LinkLine,
+    /// `[cite_start]`
+    CiteStart,
+    /// `[cite: 1]`
+    CiteMarker,
+    /// `[^1^]`
+    CopilotFootnote,
+    /// `Source: Conversation with Copilot`
+    CopilotSources,
+    /// `[citation:1]`
+    CitationPrefix,
+    /// `[1]` markers with `[1]: https://…` definitions
+    NumericDefinitions,
+}
+
+impl Signal {
+    /// Stable kebab-case identifier, e.g. `lenticular-marker`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LenticularMarker => "lenticular-marker",
+            Self::ContentReference => "content-reference",
+            Self::CiteTurn => "cite-turn",
+            Self::ChatGptTracking => "chatgpt-tracking",
+            Self::LongReport => "long-report",
+            Self::UnderscoreFootnote => "underscore-footnote",
+            Self::PerplexityChrome => "perplexity-chrome",
+            Self::LinkLine => "link-line",
+            Self::CiteStart => "cite-start",
+            Self::CiteMarker => "cite-marker",
+            Self::CopilotFootnote => "copilot-footnote",
+            Self::CopilotSources => "copilot-sources",
+            Self::CitationPrefix => "citation-prefix",
+            Self::NumericDefinitions => "numeric-definitions",
+        }
+    }
+
+    /// How strongly the signal points at each provider
+    fn weights(self) -> &'static [(Provider, f64)] {
+        use Provider::*;
+        match self {
+            Self::LenticularMarker => &[(ChatGpt, 0.8), (ChatGptDeepResearch, 0.6)],
+            Self::ContentReference => &[(ChatGpt, 0.9), (ChatGptDeepResearch, 0.7)],
+            Self::CiteTurn => &[(ChatGpt, 0.9), (ChatGptDeepResearch, 0.8)],
+            Self::ChatGptTracking => &[(ChatGpt, 0.7), (ChatGptDeepResearch, 0.6)],
+            Self::LongReport => &[(ChatGptDeepResearch, 0.7)],
+            Self::UnderscoreFootnote => &[(PerplexityExport, 0.8), (Perplexity, 0.3)],
+            Self::PerplexityChrome => &[(PerplexityExport, 0.95)],
+            Self::LinkLine => &[(Perplexity, 0.6)],
+            Self::CiteStart => &[(Gemini, 0.95)],
+            Self::CiteMarker => &[(Gemini, 0.6)],
+            Self::CopilotFootnote => &[(Copilot, 0.9)],
+            Self::CopilotSources => &[(Copilot, 0.95)],
+            Self::CitationPrefix => &[(DeepSeek, 0.9)],
+            Self::NumericDefinitions => &[(Perplexity, 0.3), (Claude, 0.2)],
+        }
+    }
+}
+
+static LENTICULAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"【[^】\n]*†[^】\n]*】").unwrap());
+static UNDERSCORE_FOOTNOTE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\^\d+_\d+\]").unwrap());
+static LINK_LINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*(?:[-*][ \t]+)?\[\d+\]\(https?://").unwrap());
+static CITE_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[cite:\s*\d+").unwrap());
+static CITATION_PREFIX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[citation:\s*\d+\]").unwrap());
+static NUMERIC_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\S\[\d+\]").unwrap());
+static NUMERIC_DEFINITION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*\[\d+\]:[ \t]*\S").unwrap());
+static HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^#{1,3}[ \t]+\S").unwrap());
+
+/// Headings a document needs before ChatGPT markers suggest a Deep Research report
+const LONG_REPORT_HEADINGS: usize = 5;
+
+/// Signals found in `markdown`
+fn signals(markdown: &str) -> Vec<Signal> {
+    let has_artifact = |artifact: Artifact| !artifact.ranges(markdown).is_empty();
+    let checks = [
+        (Signal::LenticularMarker, LENTICULAR.is_match(markdown)),
+        (
+            Signal::ContentReference,
+            has_artifact(Artifact::ContentReference),
+        ),
+        (Signal::CiteTurn, has_artifact(Artifact::CiteTurn)),
+        (
+            Signal::ChatGptTracking,
+            markdown.contains("utm_source=chatgpt.com"),
+        ),
+        (
+            Signal::UnderscoreFootnote,
+            UNDERSCORE_FOOTNOTE.is_match(markdown),
+        ),
+        (
+            Signal::PerplexityChrome,
+            has_artifact(Artifact::PerplexityChrome),
+        ),
+        (Signal::LinkLine, LINK_LINE.is_match(markdown)),
+        (Signal::CiteStart, markdown.contains("[cite_start]")),
+        (Signal::CiteMarker, CITE_MARKER.is_match(markdown)),
+        (
+            Signal::CopilotFootnote,
+            has_artifact(Artifact::CopilotFootnotes),
+        ),
+        (
+            Signal::CopilotSources,
+            has_artifact(Artifact::CopilotSources),
+        ),
+        (Signal::CitationPrefix, CITATION_PREFIX.is_match(markdown)),
+        (
+            Signal::NumericDefinitions,
+            NUMERIC_MARKER.is_match(markdown) && NUMERIC_DEFINITION.is_match(markdown),
+        ),
+    ];
+    let mut found: Vec<Signal> = checks
+        .into_iter()
+        .filter(|(_, present)| *present)
+        .map(|(signal, _)| signal)
+        .collect();
+
+    let chatgpt = found.iter().any(|signal| {
+        matches!(
+            signal,
+            Signal::LenticularMarker | Signal::ContentReference | Signal::CiteTurn
+        )
+    });
+    if chatgpt && HEADING.find_iter(markdown).count() >= LONG_REPORT_HEADINGS {
+        found.push(Signal::LongReport);
+    }
+    found
+}
+
+/// Guess which AI products produced `markdown`
+///
+/// Returns every provider with some evidence, most likely first. Each
+/// signal found adds to a provider's confidence independently, so several
+/// weak signals add up to a strong one.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{detect_source, Provider, Signal};
+///
+/// let detections = detect_source("[cite_start]Rust is fast.[cite: 1]");
+/// assert_eq!(detections[0].provider, Provider::Gemini);
+/// assert!(detections[0].confidence > 0.9);
+/// assert_eq!(detections[0].signals, [Signal::CiteStart, Signal::CiteMarker]);
+///
+/// assert!(detect_source("No citations here.").is_empty());
+/// ```
+pub fn detect_source(markdown: &str) -> Vec<Detection> {
+    let signals = signals(markdown);
+    let mut detections: Vec<Detection> = Provider::ALL
+        .iter()
+        .filter_map(|&provider| {
+            let mut unlikely = 1.0;
+            let mut evidence = Vec::new();
+            for &signal in &signals {
+                if let Some((_, weight)) = signal.weights().iter().find(|(p, _)| *p == provider) {
+                    unlikely *= 1.0 - weight;
+                    evidence.push(signal);
+                }
+            }
+            (!evidence.is_empty()).then_some(Detection {
+                provider,
+                confidence: 1.0 - unlikely,
+                signals: evidence,
+            })
+        })
+        .collect();
+    // Stable, so ties keep the order of `Provider::ALL`
+    detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    detections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(markdown: &str) -> Option<Provider> {
+        detect_source(markdown).first().map(|d| d.provider)
+    }
+
+    #[test]
+    fn test_detects_each_provider() {
+        assert_eq!(
+            top("Fact【3†source】 :contentReference[oaicite:0]{index=0}."),
+            Some(Provider::ChatGpt)
+        );
+        assert_eq!(
+            top("Fact[^1_2].\n\n[^1_2]: https://a.com"),
+            Some(Provider::PerplexityExport)
+        );
+        assert_eq!(
+            top("Fact[1].\n\n[1](https://a.com)"),
+            Some(Provider::Perplexity)
+        );
+        assert_eq!(top("Fact[^1^]."), Some(Provider::Copilot));
+        assert_eq!(top("Fact[citation:2]."), Some(Provider::DeepSeek));
+        assert_eq!(top("Fact[cite: 2]."), Some(Provider::Gemini));
+        assert_eq!(top("Plain text."), None);
+    }
+
+    #[test]
+    fn test_headings_tip_chatgpt_towards_deep_research() {
+        let mut report = String::new();
+        for i in 0..5 {
+            report.push_str(&format!(
+                "## Part {}\n\nFinding citeturn0search{}.\n\n",
+                i, i
+            ));
+        }
+        let detections = detect_source(&report);
+        assert_eq!(detections[0].provider, Provider::ChatGptDeepResearch);
+        assert!(detections[0].signals.contains(&Signal::LongReport));
+        assert_eq!(detections[1].provider, Provider::ChatGpt);
+
+        // Headings alone say nothing
+        assert!(detect_source("## A\n## B\n## C\n## D\n## E\n").is_empty());
+    }
+
+    #[test]
+    fn test_weak_signals_stay_below_threshold() {
+        let detections = detect_source("Fact[1].\n\n[1]: https://a.com");
+        assert!(detections.iter().all(|d| d.confidence < AUTO_THRESHOLD));
+    }
+}
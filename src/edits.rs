@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+/// Apply replacements to `text`, skipping any that overlap an earlier one
+pub(crate) fn splice(text: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start);
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        if range.start < last {
+            continue;
+        }
+        result.push_str(&text[last..range.start]);
+        result.push_str(&replacement);
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// A run of output text copied unchanged from the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Segment {
+    /// Input offset of the first byte of the run
+    pub input: usize,
+    /// Byte range of the run in the output
+    pub output: Range<usize>,
+}
+
+/// Tracks which input bytes survive a sequence of edits
+///
+/// Bytes of the current text copied from the input belong to exactly one
+/// segment; bytes inserted by replacements belong to none.
+#[derive(Debug, Clone)]
+pub(crate) struct Origins {
+    segments: Vec<Segment>,
+    input_len: usize,
+}
+
+impl Origins {
+    pub fn new(len: usize) -> Self {
+        let segments = if len == 0 {
+            Vec::new()
+        } else {
+            vec![Segment {
+                input: 0,
+                output: 0..len,
+            }]
+        };
+        Self {
+            segments,
+            input_len: len,
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Input offset of the byte at `offset` in the current text
+    ///
+    /// Inserted bytes map to the start of the input they replaced.
+    pub fn input_offset(&self, offset: usize) -> usize {
+        let i = self.segments.partition_point(|s| s.output.end <= offset);
+        match self.segments.get(i) {
+            Some(segment) if segment.output.start <= offset => {
+                segment.input + (offset - segment.output.start)
+            }
+            _ => i.checked_sub(1).map_or(0, |j| {
+                self.segments[j].input + self.segments[j].output.len()
+            }),
+        }
+    }
+
+    /// Input range spanned by a non-empty range of the current text
+    pub fn input_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.input_offset(range.start);
+        let last = range.end - 1;
+        let i = self.segments.partition_point(|s| s.output.end <= last);
+        let end = match self.segments.get(i) {
+            Some(segment) if segment.output.start <= last => {
+                segment.input + (last - segment.output.start) + 1
+            }
+            Some(segment) => segment.input,
+            None => self.input_len,
+        };
+        start..end.max(start)
+    }
+
+    /// Record the replacement of sorted, non-overlapping ranges of the current
+    /// text with text of the given lengths
+    pub fn replace(&mut self, edits: &[(Range<usize>, usize)]) {
+        let mut removed_before = Vec::with_capacity(edits.len() + 1);
+        let mut inserted_before = Vec::with_capacity(edits.len() + 1);
+        removed_before.push(0);
+        inserted_before.push(0);
+        for (range, inserted) in edits {
+            removed_before.push(removed_before.last().copied().unwrap_or(0) + range.len());
+            inserted_before.push(inserted_before.last().copied().unwrap_or(0) + inserted);
+        }
+        let moved = |offset: usize| {
+            let k = edits.partition_point(|(r, _)| r.end <= offset);
+            offset + inserted_before[k] - removed_before[k]
+        };
+
+        let mut segments = Vec::with_capacity(self.segments.len() + edits.len());
+        let mut keep = |segment: &Segment, kept: Range<usize>| {
+            let start = moved(kept.start);
+            segments.push(Segment {
+                input: segment.input + (kept.start - segment.output.start),
+                output: start..start + kept.len(),
+            });
+        };
+
+        for segment in &self.segments {
+            let mut start = segment.output.start;
+            let first = edits.partition_point(|(r, _)| r.end <= start);
+            for (range, _) in &edits[first..] {
+                if range.start >= segment.output.end {
+                    break;
+                }
+                if range.start > start {
+                    keep(segment, start..range.start);
+                }
+                start = start.max(range.end);
+            }
+            if start < segment.output.end {
+                keep(segment, start..segment.output.end);
+            }
+        }
+        self.segments = segments;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splice() {
+        let edits = vec![(6..9, String::new()), (1..4, "†".to_string())];
+        assert_eq!(splice("a[1] b[2].", edits), "a† b.");
+    }
+
+    #[test]
+    fn test_origins_across_deletions() {
+        let input = "A[1]  b.\n\n\n\nC";
+        let mut origins = Origins::new(input.len());
+
+        origins.replace(&[(1..4, 0)]);
+        let text = splice(input, vec![(1..4, String::new())]);
+        assert_eq!(text, "A  b.\n\n\n\nC");
+        assert_eq!(origins.input_range(1..3), 4..6);
+
+        origins.replace(&[(2..3, 0), (7..9, 0)]);
+        let text = splice(&text, vec![(2..3, String::new()), (7..9, String::new())]);
+        assert_eq!(text, "A b.\n\nC");
+        assert_eq!(origins.input_offset(1), 4);
+        assert_eq!(origins.input_offset(2), 6);
+        assert_eq!(origins.input_offset(6), input.len() - 1);
+        let segments: Vec<_> = origins
+            .segments()
+            .iter()
+            .map(|s| (s.input, s.output.clone()))
+            .collect();
+        assert_eq!(segments, vec![(0, 0..1), (4, 1..2), (6, 2..6), (12, 6..7)]);
+    }
+
+    #[test]
+    fn test_origins_across_replacements() {
+        let input = "A[1]  b[2].";
+        let mut origins = Origins::new(input.len());
+
+        origins.replace(&[(1..4, 3), (7..10, 3)]);
+        let text = splice(
+            input,
+            vec![(1..4, "†".to_string()), (7..10, "†".to_string())],
+        );
+        assert_eq!(text, "A†  b†.");
+        let segments: Vec<_> = origins
+            .segments()
+            .iter()
+            .map(|s| (s.input, s.output.clone()))
+            .collect();
+        assert_eq!(segments, vec![(0, 0..1), (4, 4..7), (10, 10..11)]);
+
+        assert_eq!(origins.input_offset(2), 1);
+        assert_eq!(origins.input_range(1..5), 1..5);
+        assert_eq!(origins.input_range(7..10), 7..10);
+    }
+}
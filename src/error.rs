@@ -7,6 +7,9 @@ pub enum RemoverError {
     #[error("Invalid regex pattern: {0}")]
     InvalidPattern(String),
 
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
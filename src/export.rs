@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{CitationAnalysis, Source};
+use crate::names::named_enum;
+
+/// Bibliography file format for [`export_sources`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum SourcesFormat {
+    /// CSL-JSON array, as read by Zotero, Pandoc and citeproc
+    #[default]
+    CslJson,
+    /// BibTeX / BibLaTeX `@online` entries
+    BibTex,
+    /// RIS records (`TY  - ELEC`)
+    Ris,
+}
+
+named_enum! {
+    SourcesFormat, "sources format" {
+        CslJson => "csl-json" | "csl" | "json",
+        BibTex => "bibtex" | "bib",
+        Ris => "ris",
+    }
+}
+
+impl SourcesFormat {
+    /// Conventional file extension for the format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::CslJson => "json",
+            Self::BibTex => "bib",
+            Self::Ris => "ris",
+        }
+    }
+}
+
+impl CitationAnalysis {
+    /// Serialize the extracted sources, using today's date (UTC) as the access date
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{extract_citations, SourcesFormat};
+    ///
+    /// let analysis = extract_citations("Fact[1].\n\n[1]: https://example.com \"Example\"");
+    /// let bibtex = analysis.export_sources(SourcesFormat::BibTex);
+    /// assert!(bibtex.starts_with("@online{ref1,"));
+    /// ```
+    pub fn export_sources(&self, format: SourcesFormat) -> String {
+        export_sources(&self.sources, format, Some(&today()))
+    }
+}
+
+/// Serialize sources to a bibliography format
+///
+/// `accessed` is an ISO 8601 date (`2025-01-31`) recorded as the access date
+/// of every source; pass `None` to omit it.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{export_sources, extract_citations, SourcesFormat};
+///
+/// let analysis = extract_citations("Fact[1].\n\n[1]: https://example.com \"Example\"");
+/// let ris = export_sources(&analysis.sources, SourcesFormat::Ris, Some("2025-01-31"));
+/// assert_eq!(
+///     ris,
+///     "TY  - ELEC\nID  - ref1\nTI  - Example\nUR  - https://example.com\nY2  - 2025/01/31/\nER  - \n"
+/// );
+/// ```
+pub fn export_sources(sources: &[Source], format: SourcesFormat, accessed: Option<&str>) -> String {
+    match format {
+        SourcesFormat::CslJson => csl_json(sources, accessed),
+        SourcesFormat::BibTex => bibtex(sources, accessed),
+        SourcesFormat::Ris => ris(sources, accessed),
+    }
+}
+
+fn csl_json(sources: &[Source], accessed: Option<&str>) -> String {
+    let items: Vec<String> = sources
+        .iter()
+        .zip(keys(sources))
+        .map(|(source, key)| {
+            let mut fields = vec![
+                format!("\"id\": {}", json_string(&key)),
+                format!(
+                    "\"type\": \"{}\"",
+                    if source.url.is_some() {
+                        "webpage"
+                    } else {
+                        "document"
+                    }
+                ),
+                format!("\"title\": {}", json_string(&title(source))),
+            ];
+            if let Some(url) = &source.url {
+                fields.push(format!("\"URL\": {}", json_string(url)));
+            }
+            if let Some(parts) = accessed.and_then(date_parts) {
+                fields.push(format!(
+                    "\"accessed\": {{\"date-parts\": [[{}, {}, {}]]}}",
+                    parts.0, parts.1, parts.2
+                ));
+            }
+            format!("  {{\n    {}\n  }}", fields.join(",\n    "))
+        })
+        .collect();
+
+    if items.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+}
+
+fn bibtex(sources: &[Source], accessed: Option<&str>) -> String {
+    sources
+        .iter()
+        .zip(keys(sources))
+        .map(|(source, key)| {
+            let mut fields = vec![format!("  title = {{{}}}", latex_escape(&title(source)))];
+            if let Some(url) = &source.url {
+                fields.push(format!("  url = {{{}}}", url));
+            }
+            if let Some(date) = accessed {
+                fields.push(format!("  urldate = {{{}}}", date));
+            }
+            format!("@online{{{},\n{}\n}}\n", key, fields.join(",\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn ris(sources: &[Source], accessed: Option<&str>) -> String {
+    sources
+        .iter()
+        .zip(keys(sources))
+        .map(|(source, key)| {
+            let mut record = format!("TY  - ELEC\nID  - {}\nTI  - {}\n", key, title(source));
+            if let Some(url) = &source.url {
+                record.push_str(&format!("UR  - {}\n", url));
+            }
+            if let Some((year, month, day)) = accessed.and_then(date_parts) {
+                record.push_str(&format!("Y2  - {:04}/{:02}/{:02}/\n", year, month, day));
+            }
+            record.push_str("ER  - \n");
+            record
+        })
+        .collect()
+}
+
+/// Citation keys: `ref` followed by the label, non-alphanumerics replaced by `_`
+///
+/// Labels that map to the same key, like `[1]` and `[^1]`, get a `_2`, `_3`,
+/// … suffix after the first.
+fn keys(sources: &[Source]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    sources
+        .iter()
+        .map(|source| {
+            let label: String = source
+                .label
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            let base = format!("ref{}", label);
+            let mut key = base.clone();
+            let mut n = 1;
+            while !seen.insert(key.clone()) {
+                n += 1;
+                key = format!("{}_{}", base, n);
+            }
+            key
+        })
+        .collect()
+}
+
+/// Title of a source: its own title, the entry text, or the URL
+fn title(source: &Source) -> String {
+    if let Some(title) = &source.title {
+        return title.clone();
+    }
+    let body = source
+        .raw
+        .split_once(']')
+        .map_or(source.raw.as_str(), |(_, rest)| rest)
+        .trim_start_matches(':')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match &source.url {
+        Some(url) if body.trim_matches(['(', ')', '<', '>']) == url => url.clone(),
+        _ => body,
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn latex_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parse an ISO 8601 `YYYY-MM-DD` date
+fn date_parts(date: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = date.splitn(3, '-').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// Today's date (UTC) as `YYYY-MM-DD`
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Convert days since 1970-01-01 to a proleptic Gregorian date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_citations;
+
+    const DOC: &str = "A[1] B[^2_1].\n\n[1]: https://a.com/x \"Growth & Decline\"\n[^2_1]: https://b.com\n[3] Smith, J. (2024). Paper.";
+
+    #[test]
+    fn test_csl_json() {
+        let analysis = extract_citations(DOC);
+        let json = export_sources(
+            &analysis.sources,
+            SourcesFormat::CslJson,
+            Some("2025-01-31"),
+        );
+        assert!(json.starts_with("[\n  {\n    \"id\": \"ref1\",\n    \"type\": \"webpage\""));
+        assert!(json.contains("\"title\": \"Growth & Decline\""));
+        assert!(json.contains("\"id\": \"ref2_1\""));
+        assert!(json.contains("\"title\": \"https://b.com\""));
+        assert!(
+            json.contains("\"type\": \"document\",\n    \"title\": \"Smith, J. (2024). Paper.\"")
+        );
+        assert!(json.contains("\"accessed\": {\"date-parts\": [[2025, 1, 31]]}"));
+    }
+
+    #[test]
+    fn test_bibtex() {
+        let analysis = extract_citations(DOC);
+        let bib = export_sources(&analysis.sources, SourcesFormat::BibTex, Some("2025-01-31"));
+        assert!(bib.starts_with(
+            "@online{ref1,\n  title = {Growth \\& Decline},\n  url = {https://a.com/x},\n  urldate = {2025-01-31}\n}\n"
+        ));
+        assert_eq!(bib.matches("@online{").count(), 3);
+    }
+
+    #[test]
+    fn test_ris_without_date() {
+        let analysis = extract_citations("[1](https://a.com)");
+        let ris = export_sources(&analysis.sources, SourcesFormat::Ris, None);
+        assert_eq!(
+            ris,
+            "TY  - ELEC\nID  - ref1\nTI  - https://a.com\nUR  - https://a.com\nER  - \n"
+        );
+    }
+
+    #[test]
+    fn test_json_string_escaping() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+    }
+
+    #[test]
+    fn test_keys_are_unique() {
+        let analysis = extract_citations(
+            "A[1] B[^1] C[^1_2].\n\n[1]: https://a.com\n[^1]: https://b.com\n[^1_2]: https://c.com",
+        );
+        assert_eq!(keys(&analysis.sources), ["ref1", "ref1_2", "ref1_2_2"]);
+        let bib = export_sources(&analysis.sources, SourcesFormat::BibTex, None);
+        assert!(bib.contains("@online{ref1,") && bib.contains("@online{ref1_2,"));
+    }
+
+    #[test]
+    fn test_latex_escape() {
+        assert_eq!(
+            latex_escape(r"C:\dir ~home ^x 50% {a}"),
+            r"C:\textbackslash{}dir \textasciitilde{}home \textasciicircum{}x 50\% \{a\}"
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(20_119), (2025, 1, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("csl-json".parse(), Ok(SourcesFormat::CslJson));
+        assert_eq!("BibTeX".parse(), Ok(SourcesFormat::BibTex));
+        assert_eq!("ris".parse(), Ok(SourcesFormat::Ris));
+        assert_eq!("bib".parse(), Ok(SourcesFormat::BibTex));
+        assert!("xml".parse::<SourcesFormat>().is_err());
+        for &format in SourcesFormat::ALL {
+            assert_eq!(format.to_string().parse(), Ok(format));
+        }
+    }
+}
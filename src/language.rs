@@ -0,0 +1,141 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::names::named_enum;
+
+/// Language whose reference section headings are recognised
+///
+/// Each language contributes the keywords that head a reference section, such
+/// as `Quellen` in `## Quellen`. Add them to a configuration with
+/// [`RemoverConfig::with_language`](crate::RemoverConfig::with_language).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Language {
+    English,
+    German,
+    Spanish,
+    French,
+    Portuguese,
+    Italian,
+    Dutch,
+    Russian,
+    Chinese,
+    Japanese,
+    Korean,
+}
+
+named_enum! {
+    Language, "language" {
+        English => "english",
+        German => "german",
+        Spanish => "spanish",
+        French => "french",
+        Portuguese => "portuguese",
+        Italian => "italian",
+        Dutch => "dutch",
+        Russian => "russian",
+        Chinese => "chinese",
+        Japanese => "japanese",
+        Korean => "korean",
+    }
+}
+
+impl Language {
+    /// Headings that start a reference section, matched case-sensitively
+    pub fn header_keywords(self) -> &'static [&'static str] {
+        match self {
+            Self::English => &[
+                "References",
+                "Reference",
+                "Citations",
+                "Citation",
+                "Sources",
+                "Source",
+                "Bibliography",
+                "Notes",
+                "Note",
+            ],
+            Self::German => &[
+                "Quellen",
+                "Quelle",
+                "Quellenangaben",
+                "Referenzen",
+                "Literatur",
+                "Literaturverzeichnis",
+                "Einzelnachweise",
+                "Anmerkungen",
+                "Fußnoten",
+            ],
+            Self::Spanish => &["Referencias", "Fuentes", "Bibliografía", "Citas", "Notas"],
+            Self::French => &[
+                "Références",
+                "Sources",
+                "Bibliographie",
+                "Citations",
+                "Notes",
+            ],
+            Self::Portuguese => &["Referências", "Fontes", "Bibliografia", "Citações", "Notas"],
+            Self::Italian => &["Riferimenti", "Fonti", "Bibliografia", "Citazioni", "Note"],
+            Self::Dutch => &["Bronnen", "Referenties", "Bibliografie", "Noten"],
+            Self::Russian => &[
+                "Источники",
+                "Ссылки",
+                "Литература",
+                "Список литературы",
+                "Примечания",
+            ],
+            Self::Chinese => &[
+                "参考文献",
+                "参考资料",
+                "参考来源",
+                "资料来源",
+                "引用来源",
+                "来源",
+                "引用",
+                "注释",
+                "參考文獻",
+                "參考資料",
+                "資料來源",
+                "來源",
+            ],
+            Self::Japanese => &["参考文献", "参考資料", "出典", "引用", "脚注", "注釈"],
+            Self::Korean => &["참고 문헌", "참고문헌", "참고 자료", "출처", "각주"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::Patterns;
+
+    #[test]
+    fn test_every_language_matches_its_keywords() {
+        let keywords: Vec<_> = Language::ALL
+            .iter()
+            .flat_map(|l| l.header_keywords().iter().copied())
+            .collect();
+        let patterns = Patterns::with_vocabulary(&["source"], &keywords);
+        for keyword in keywords {
+            assert!(patterns
+                .reference_header
+                .is_match(&format!("## {}", keyword)));
+        }
+        assert!(patterns.reference_header.is_match("##参考文献"));
+        assert!(!patterns.reference_header.is_match("## Einleitung"));
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for &language in Language::ALL {
+            assert_eq!(language.to_string().parse(), Ok(language));
+        }
+        assert_eq!("German".parse(), Ok(Language::German));
+        assert!("klingon".parse::<Language>().is_err());
+    }
+}
@@ -23,6 +23,23 @@
 //! - ✅ Remove reference link lists `[1]: https://...`
 //! - ✅ Remove reference section headers `## References`
 //! - ✅ Remove bibliographic entries
+//! - ✅ Convert citations to CommonMark footnotes or inline links instead of removing them
+//! - ✅ Renumber remaining citations and merge duplicate sources
+//! - ✅ Normalize mixed citation syntaxes into one style
+//! - ✅ Extract citations and sources into a typed model
+//! - ✅ Export sources as CSL-JSON, BibTeX or RIS
+//! - ✅ Removal reports with rule IDs, spans and per-category counts
+//! - ✅ Offset maps between cleaned output and original input
+//! - ✅ Minimal text edits in UTF-8 or UTF-16 offsets for editors and CRDTs
+//! - ✅ Reversible removal: restore citations from a sidecar, even after edits
+//! - ✅ Replace citations with a placeholder, an HTML comment or custom output
+//! - ✅ Custom inline, definition and header patterns, validated when the remover is built
+//! - ✅ Configurable named-citation prefixes and reference headings, with language packs
+//! - ✅ Full-width `［1］` markers and CJK spacing
+//! - ✅ Custom pipelines of built-in and user-defined stages
+//! - ✅ Provider profiles for ChatGPT, Perplexity, Claude, Gemini, Copilot and DeepSeek
+//! - ✅ Detect the provider from the citation syntaxes in a document
+//! - ✅ TOML and JSON configuration files with named profiles (`serde` feature)
 //! - ✅ Preserve markdown formatting
 //! - ✅ Whitespace normalization
 //! - ✅ Ultra-fast performance (100+ MB/s throughput)
@@ -42,14 +59,55 @@
 //! let result = remover.remove("Text with citations[1].");
 //! ```
 
+mod analysis;
+mod artifacts;
+mod blocks;
+mod builder;
+mod citations;
 mod config;
+#[cfg(feature = "serde")]
+mod config_file;
+mod convert;
+mod detect;
+mod edits;
 mod error;
+mod export;
+mod language;
+mod names;
+mod normalize;
+mod offset_map;
 mod patterns;
+mod pipeline;
+mod position;
+mod provider;
 mod remover;
+mod replacement;
+mod report;
+mod sidecar;
+mod text_edit;
 
+pub use analysis::{Citation, CitationAnalysis, Source, SourceKind};
+pub use artifacts::Artifact;
+pub use builder::CitationRemoverBuilder;
+pub use citations::CitationKind;
 pub use config::{RemovalMode, RemoverConfig};
+#[cfg(feature = "serde")]
+pub use config_file::ConfigFile;
+pub use convert::{CitationFormat, LinkStyle};
+pub use detect::{detect_source, Detection, Signal};
 pub use error::{RemoverError, Result};
+pub use export::{export_sources, SourcesFormat};
+pub use language::Language;
+pub use normalize::{CitationStyle, MarkerPlacement, NormalizeOptions};
+pub use offset_map::{OffsetMap, PreservedSegment};
+pub use patterns::Pattern;
+pub use pipeline::{BuiltinStage, FnStage, Pipeline, Stage, StageContext, StageEdit};
+pub use provider::Provider;
 pub use remover::CitationRemover;
+pub use replacement::{CitationMatch, Replacement, Replacer};
+pub use report::{Removal, RemovalCategory, RemovalCounts, RemovalReport, RuleId};
+pub use sidecar::{CitationSidecar, Restored, SidecarEntry, SidecarEntryKind};
+pub use text_edit::{OffsetEncoding, TextEdit};
 
 /// Main entry point - remove citations from markdown with default settings
 ///
@@ -82,6 +140,122 @@ pub fn remove_citations_with_config(markdown: &str, config: RemoverConfig) -> St
     CitationRemover::with_config(config).remove(markdown)
 }
 
+/// Remove citations with the profile of the provider detected in `markdown`
+///
+/// Falls back to the default settings when no provider is likely; see
+/// [`RemoverConfig::auto`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::remove_citations_auto;
+///
+/// let input = "[cite_start]Rust is fast.[cite: 1, 2]";
+/// assert_eq!(remove_citations_auto(input), "Rust is fast.");
+/// ```
+pub fn remove_citations_auto(markdown: &str) -> String {
+    remove_citations_with_config(markdown, RemoverConfig::auto(markdown))
+}
+
+/// Remove citations with default settings and report what was removed
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::remove_citations_with_report;
+///
+/// let (output, report) = remove_citations_with_report("Text[1]  here.\n\n## Sources\n[1]: https://a.com");
+/// assert_eq!(output.trim(), "Text here.");
+/// assert_eq!(report.counts.inline, 1);
+/// assert_eq!(report.counts.headers, 1);
+/// assert_eq!(report.counts.definitions, 1);
+/// ```
+pub fn remove_citations_with_report(markdown: &str) -> (String, RemovalReport) {
+    CitationRemover::new().remove_with_report(markdown)
+}
+
+/// Convert citations into another format with default settings
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{convert_citations, CitationFormat};
+///
+/// let input = "Fact[^1_1].\n\n[^1_1]: https://example.com";
+/// let output = convert_citations(input, CitationFormat::Footnotes);
+/// assert_eq!(output, "Fact[^1].\n\n[^1]: <https://example.com>");
+/// ```
+pub fn convert_citations(markdown: &str, format: CitationFormat) -> String {
+    CitationRemover::new().convert(markdown, format)
+}
+
+/// Renumber citations from 1 and merge duplicate sources with default settings
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::renumber_citations;
+///
+/// let input = "Fact[4] and fact[7].\n\n[4]: https://a.com\n[7]: https://b.com";
+/// let output = renumber_citations(input);
+/// assert_eq!(output, "Fact[1] and fact[2].\n\n[1]: https://a.com\n[2]: https://b.com");
+/// ```
+pub fn renumber_citations(markdown: &str) -> String {
+    CitationRemover::new().renumber(markdown)
+}
+
+/// Normalize mixed citation syntaxes into one style with default settings
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{normalize_citations, NormalizeOptions};
+///
+/// let input = "A[^1_3] and B【2†source】.\n\n[^1_3]: https://a.com";
+/// let output = normalize_citations(input, NormalizeOptions::default());
+/// assert_eq!(output, "A[1] and B[2].\n\n[1]: https://a.com");
+/// ```
+pub fn normalize_citations(markdown: &str, options: NormalizeOptions) -> String {
+    CitationRemover::new().normalize(markdown, options)
+}
+
+/// Extract inline citations and their sources without changing the document
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::extract_citations;
+///
+/// let analysis = extract_citations("Fact[1][2].\n\n[1]: https://example.com");
+/// assert_eq!(analysis.citations.len(), 2);
+/// assert_eq!(analysis.unresolved().count(), 1);
+/// assert_eq!(analysis.sources[0].citations, vec![0]);
+/// ```
+pub fn extract_citations(markdown: &str) -> CitationAnalysis {
+    CitationRemover::new().analyze(markdown)
+}
+
+/// Put citations removed by [`CitationRemover::remove_reversible`] back into `text`
+///
+/// `text` may have been edited since it was cleaned; see
+/// [`CitationSidecar::restore`] for how pieces are re-anchored.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{restore_citations, CitationRemover};
+///
+/// let input = "Water boils at 100 °C[1] at sea level.";
+/// let (output, sidecar) = CitationRemover::new().remove_reversible(input);
+/// assert_eq!(output, "Water boils at 100 °C at sea level.");
+///
+/// let restored = restore_citations("Pure water boils at 100 °C at sea level.", &sidecar);
+/// assert_eq!(restored.text, "Pure water boils at 100 °C[1] at sea level.");
+/// ```
+pub fn restore_citations(text: &str, sidecar: &CitationSidecar) -> Restored {
+    sidecar.restore(text)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
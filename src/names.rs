@@ -0,0 +1,61 @@
+//! Stable names for the public option enums
+
+/// Give a fieldless enum a stable name per variant
+///
+/// Generates `ALL`, `as_str`, `FromStr` (case-insensitive, with optional
+/// aliases after `|`) and `Display`:
+///
+/// ```text
+/// named_enum! {
+///     SourcesFormat, "sources format" {
+///         CslJson => "csl-json" | "csl" | "json",
+///         BibTex => "bibtex" | "bib",
+///         Ris => "ris",
+///     }
+/// }
+/// ```
+macro_rules! named_enum {
+    ($ty:ident, $noun:literal {
+        $($variant:ident => $name:literal $(| $alias:literal)*),+ $(,)?
+    }) => {
+        impl $ty {
+            #[doc = concat!("Every ", $noun)]
+            pub const ALL: &'static [$ty] = &[$(Self::$variant),+];
+
+            /// Stable kebab-case identifier, as parsed by `FromStr` and
+            /// printed by `Display`
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name),+
+                }
+            }
+        }
+
+        impl std::str::FromStr for $ty {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.to_ascii_lowercase().as_str() {
+                    $($name $(| $alias)* => Ok(Self::$variant),)+
+                    _ => {
+                        let names: Vec<_> = Self::ALL.iter().map(|item| item.as_str()).collect();
+                        Err(format!(
+                            "unknown {} '{}' (expected {})",
+                            $noun,
+                            s,
+                            names.join(", ")
+                        ))
+                    }
+                }
+            }
+        }
+
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+pub(crate) use named_enum;
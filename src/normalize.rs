@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::{self, Block, BlockKind};
+use crate::citations::{self, Definition, Marker, Resolver};
+use crate::convert::footnote_text;
+use crate::edits::splice;
+use crate::patterns::Patterns;
+
+/// Target marker syntax for [`CitationRemover::normalize`](crate::CitationRemover::normalize)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum CitationStyle {
+    /// `[1]` markers with `[1]: url "Title"` definitions
+    #[default]
+    Numeric,
+    /// `[^1]` markers with `[^1]: [Title](url)` definitions
+    Footnote,
+}
+
+/// Where markers sit relative to adjacent punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum MarkerPlacement {
+    /// Leave markers where they are
+    #[default]
+    Preserve,
+    /// `fact[1].` - move markers in front of the punctuation
+    BeforePunctuation,
+    /// `fact.[1]` - move markers after the punctuation
+    AfterPunctuation,
+}
+
+/// Options for citation style normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NormalizeOptions {
+    /// Syntax for markers and definitions
+    pub style: CitationStyle,
+    /// Placement of markers relative to punctuation
+    pub placement: MarkerPlacement,
+}
+
+/// Punctuation that markers are moved across
+const PUNCTUATION: [char; 6] = ['.', ',', ';', ':', '!', '?'];
+
+/// Renumber markers and definitions from 1 in order of first appearance
+///
+/// Every marker keeps its syntax (`[4]` → `[2]`, `[^1_5]` → `[^3]`) and every
+/// definition keeps its layout, but definitions are reordered into the slots the
+/// reference block already occupies. Definitions whose URLs normalize to the same
+/// address are merged into the first one. Definitions no marker refers to are
+/// kept and numbered after the cited ones, and markers without a definition
+/// are numbered last.
+pub(crate) fn renumber(text: &str, patterns: &Patterns) -> String {
+    rewrite(text, patterns, None, MarkerPlacement::Preserve)
+}
+
+/// Renumber like [`renumber`], rewriting markers and definitions into one style
+pub(crate) fn normalize(text: &str, patterns: &Patterns, options: NormalizeOptions) -> String {
+    rewrite(text, patterns, Some(options.style), options.placement)
+}
+
+fn rewrite(
+    text: &str,
+    patterns: &Patterns,
+    style: Option<CitationStyle>,
+    placement: MarkerPlacement,
+) -> String {
+    let blocks = blocks::scan(text, patterns);
+    let markers = citations::markers(text, patterns, &blocks, &blocks::protected_ranges(text));
+    let mut definitions = citations::definitions(text, &blocks);
+    definitions.extend(named_definitions(text, &blocks, &markers));
+    definitions.sort_by_key(|definition| definition.range.start);
+    let resolver = Resolver::new(&definitions);
+
+    // Group duplicate definitions; each group is represented by its first member
+    let mut group_of_url: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<usize> = Vec::new();
+    let group_of: Vec<usize> = definitions
+        .iter()
+        .enumerate()
+        .map(|(i, definition)| {
+            let mut new_group = || {
+                groups.push(i);
+                groups.len() - 1
+            };
+            match definition.url.as_deref().map(normalize_url) {
+                Some(url) => *group_of_url.entry(url).or_insert_with(new_group),
+                None => new_group(),
+            }
+        })
+        .collect();
+
+    // Number groups by first citation, then uncited groups, then unresolved
+    // marker ids, so that the definitions run from 1 without gaps
+    let resolved: Vec<Option<usize>> = markers
+        .iter()
+        .map(|marker| resolver.resolve(marker).map(|i| group_of[i]))
+        .collect();
+    let mut group_numbers: HashMap<usize, usize> = HashMap::new();
+    let mut unresolved_numbers: HashMap<&str, usize> = HashMap::new();
+    let mut next = 1..;
+
+    for group in resolved.iter().flatten().copied().chain(0..groups.len()) {
+        group_numbers
+            .entry(group)
+            .or_insert_with(|| next.next().unwrap_or_default());
+    }
+    for (marker, group) in markers.iter().zip(&resolved) {
+        if group.is_none() {
+            unresolved_numbers
+                .entry(marker.id.as_str())
+                .or_insert_with(|| next.next().unwrap_or_default());
+        }
+    }
+
+    let mut rewritten: Vec<String> = Vec::with_capacity(markers.len());
+    for (marker, group) in markers.iter().zip(&resolved) {
+        let number = match group {
+            Some(group) => group_numbers[group],
+            None => unresolved_numbers[marker.id.as_str()],
+        };
+        rewritten.push(match style {
+            None => format!(
+                "{}{}{}",
+                &text[marker.range.start..marker.id_range.start],
+                number,
+                &text[marker.id_range.end..marker.range.end]
+            ),
+            Some(CitationStyle::Numeric) => format!("[{}]", number),
+            Some(CitationStyle::Footnote) => format!("[^{}]", number),
+        });
+    }
+
+    let mut edits = place_runs(text, &markers, rewritten, placement);
+
+    // Fill the existing definition slots with the groups in number order
+    let mut ordered: Vec<usize> = (0..groups.len()).collect();
+    ordered.sort_by_key(|group| group_numbers[group]);
+
+    for (slot, definition) in definitions.iter().enumerate() {
+        let replacement = match ordered.get(slot) {
+            Some(&group) => {
+                let source = &definitions[groups[group]];
+                let number = group_numbers[&group];
+                let content = match style {
+                    None => relabel(text, source, number),
+                    Some(style) => restyle(source, number, style),
+                };
+                format!(
+                    "{}{}",
+                    content,
+                    line_ending(&text[definition.range.clone()])
+                )
+            }
+            None => String::new(),
+        };
+        edits.push((definition.range.clone(), replacement));
+    }
+
+    splice(text, edits)
+}
+
+/// Definitions like `[e]: url` that named markers like `[ref:e]` refer to
+///
+/// The scanner leaves such lines alone, since they are ordinary link reference
+/// definitions to cleaning, but they are renumbered with their markers.
+fn named_definitions(text: &str, blocks: &[Block], markers: &[Marker]) -> Vec<Definition> {
+    let labels: HashSet<String> = markers
+        .iter()
+        .filter(|marker| marker.prefix.is_some())
+        .flat_map(Marker::candidate_labels)
+        .collect();
+    if labels.is_empty() {
+        return Vec::new();
+    }
+
+    let mut definitions = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let range = start..start + line.len();
+        start = range.end;
+        let Some((label, _)) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("]:"))
+        else {
+            continue;
+        };
+        let scanned = blocks
+            .iter()
+            .any(|block| block.range.start <= range.start && range.start < block.range.end);
+        if labels.contains(label) && !scanned {
+            let block = Block {
+                kind: BlockKind::LinkDefinition,
+                range,
+                lines: 0..0,
+            };
+            definitions.extend(Definition::parse(text, &block));
+        }
+    }
+    definitions
+}
+
+/// Build one edit per run of adjacent markers, moving runs across punctuation
+fn place_runs(
+    text: &str,
+    markers: &[Marker],
+    rewritten: Vec<String>,
+    placement: MarkerPlacement,
+) -> Vec<(Range<usize>, String)> {
+    let mut runs: Vec<(Range<usize>, String)> = Vec::new();
+    for (marker, replacement) in markers.iter().zip(rewritten) {
+        match runs.last_mut() {
+            Some((range, run)) if range.end == marker.range.start => {
+                range.end = marker.range.end;
+                run.push_str(&replacement);
+            }
+            _ => runs.push((marker.range.clone(), replacement)),
+        }
+    }
+
+    for (range, run) in &mut runs {
+        match placement {
+            MarkerPlacement::Preserve => {}
+            MarkerPlacement::BeforePunctuation => {
+                // `word. [1]` or `word.[1]` → `word[1].`
+                let before = text[..range.start].trim_end_matches(' ');
+                let word = before.trim_end_matches(PUNCTUATION);
+                let punctuation = &before[word.len()..];
+                if !punctuation.is_empty() && word.ends_with(|c: char| !c.is_whitespace()) {
+                    range.start = word.len();
+                    run.push_str(punctuation);
+                }
+            }
+            MarkerPlacement::AfterPunctuation => {
+                // `word[1].` → `word.[1]`
+                let after = &text[range.end..];
+                let punctuation_len = after.len() - after.trim_start_matches(PUNCTUATION).len();
+                if punctuation_len > 0 {
+                    run.insert_str(0, &after[..punctuation_len]);
+                    range.end += punctuation_len;
+                }
+            }
+        }
+    }
+
+    runs
+}
+
+/// Definition text in the target style
+fn restyle(definition: &Definition, number: usize, style: CitationStyle) -> String {
+    match style {
+        CitationStyle::Footnote => format!("[^{}]: {}", number, footnote_text(definition)),
+        CitationStyle::Numeric => match (&definition.url, &definition.title) {
+            (Some(url), Some(title)) => {
+                format!("[{}]: {} \"{}\"", number, url, title.replace('"', "\\\""))
+            }
+            (Some(url), None)
+                if definition.is_bare_url() || definition.kind != BlockKind::Entry =>
+            {
+                format!("[{}]: {}", number, url)
+            }
+            _ => format!("[{}] {}", number, definition.text),
+        },
+    }
+}
+
+/// Line terminator at the end of a block
+fn line_ending(block: &str) -> &str {
+    &block[block.trim_end_matches(['\r', '\n']).len()..]
+}
+
+/// Text of `definition` with a new label, without its line terminator
+fn relabel(text: &str, definition: &Definition, number: usize) -> String {
+    let raw = text[definition.range.clone()].trim_end_matches(['\r', '\n']);
+    // The label sits between the opening bracket (and caret) and the first `]`
+    let start = if raw.starts_with("[^") { 2 } else { 1 };
+    let end = raw.find(']').unwrap_or(start).max(start);
+    format!("{}{}{}", &raw[..start], number, &raw[end..])
+}
+
+/// Normalize a URL for duplicate detection
+///
+/// Ignores the scheme, a leading `www.`, host case, fragments, trailing slashes
+/// and `utm_*` tracking parameters.
+pub(crate) fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_start_matches('<').trim_end_matches('>');
+    let url = url.split('#').next().unwrap_or(url);
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+
+    let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (host, path) = location.split_once('/').unwrap_or((location, ""));
+    let host = host.to_ascii_lowercase();
+    let host = host.trim_start_matches("www.");
+
+    let query: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("utm_"))
+        .collect();
+
+    let mut normalized = format!("{}/{}", host, path.trim_end_matches('/'));
+    if !query.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&query.join("&"));
+    }
+    normalized.trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(text: &str) -> String {
+        renumber(text, Patterns::get())
+    }
+
+    fn run_normalize(text: &str, style: CitationStyle, placement: MarkerPlacement) -> String {
+        normalize(text, Patterns::get(), NormalizeOptions { style, placement })
+    }
+
+    #[test]
+    fn test_renumber_closes_gaps() {
+        let text = "A[4] B[7] C[4].\n\n[4]: https://a.com\n[7]: https://b.com";
+        assert_eq!(
+            run(text),
+            "A[1] B[2] C[1].\n\n[1]: https://a.com\n[2]: https://b.com"
+        );
+    }
+
+    #[test]
+    fn test_renumber_reorders_definitions() {
+        let text = "A[2] B[1].\n\n[1]: https://one.com\n\n[2]: https://two.com\n";
+        assert_eq!(
+            run(text),
+            "A[1] B[2].\n\n[1]: https://two.com\n\n[2]: https://one.com\n"
+        );
+    }
+
+    #[test]
+    fn test_renumber_merges_duplicate_urls() {
+        let text = "A[1] B[2] C[3].\n\n[1]: https://a.com/x\n[2]: http://www.a.com/x/?utm_source=chatgpt.com\n[3]: https://c.com";
+        assert_eq!(
+            run(text),
+            "A[1] B[1] C[2].\n\n[1]: https://a.com/x\n[2]: https://c.com\n"
+        );
+    }
+
+    #[test]
+    fn test_renumber_keeps_marker_syntax() {
+        let text = "A[^1_5] B[source:9].\n\n[^1_5]: https://a.com\n[9]: https://b.com";
+        assert_eq!(
+            run(text),
+            "A[^1] B[source:2].\n\n[^1]: https://a.com\n[2]: https://b.com"
+        );
+    }
+
+    #[test]
+    fn test_renumber_named_markers() {
+        let text = "A[ref:e] B[note:n] C[ref:e].\n\n[e]: https://a.com\n[n]: https://b.com";
+        assert_eq!(
+            run(text),
+            "A[ref:1] B[note:2] C[ref:1].\n\n[1]: https://a.com\n[2]: https://b.com"
+        );
+
+        // The prefix is kept even when it contains the identifier
+        let text = "A[source:e].\n\n[source:e]: https://a.com";
+        assert_eq!(run(text), "A[source:1].\n\n[source:1]: https://a.com");
+    }
+
+    #[test]
+    fn test_renumber_leaves_code_alone() {
+        let text = "```\narr[5]\n```\n\nUse `m[7]`. A[4].\n\n[4]: https://a.com";
+        assert_eq!(
+            run(text),
+            "```\narr[5]\n```\n\nUse `m[7]`. A[1].\n\n[1]: https://a.com"
+        );
+    }
+
+    #[test]
+    fn test_renumber_uncited_definitions_last() {
+        let text = "A[3].\n\n[1]: https://one.com\n[3]: https://three.com";
+        assert_eq!(
+            run(text),
+            "A[1].\n\n[1]: https://three.com\n[2]: https://one.com"
+        );
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("HTTPS://WWW.Example.com/Path/?utm_source=x&id=2#top"),
+            "example.com/Path?id=2"
+        );
+        assert_eq!(normalize_url("http://example.com/"), "example.com");
+    }
+
+    #[test]
+    fn test_normalize_mixed_syntaxes_to_numeric() {
+        let text = "A[^1_3] B【2†source】 C[source:4] D[cite: 5].\n\n[^1_3]: https://a.com\n[4](https://d.com)\n[5] Smith (2024). Paper.";
+        assert_eq!(
+            run_normalize(text, CitationStyle::Numeric, MarkerPlacement::Preserve),
+            "A[1] B[4] C[2] D[3].\n\n[1]: https://a.com\n[2]: https://d.com\n[3] Smith (2024). Paper."
+        );
+    }
+
+    #[test]
+    fn test_normalize_to_footnotes() {
+        let text = "A[2].\n\n[2]: https://a.com \"Alpha\"";
+        assert_eq!(
+            run_normalize(text, CitationStyle::Footnote, MarkerPlacement::Preserve),
+            "A[^1].\n\n[^1]: [Alpha](https://a.com)"
+        );
+    }
+
+    #[test]
+    fn test_normalize_leaves_code_alone() {
+        let text = "```\narr[5]\n```\n\nA[2] `m[7]`.\n\n[2]: https://a.com";
+        assert_eq!(
+            run_normalize(text, CitationStyle::Footnote, MarkerPlacement::Preserve),
+            "```\narr[5]\n```\n\nA[^1] `m[7]`.\n\n[^1]: <https://a.com>"
+        );
+    }
+
+    #[test]
+    fn test_unresolved_markers_leave_no_gaps() {
+        let text = "A[7] B[2] C[7] D[3].\n\n[2]: https://b.com\n[3]: https://c.com";
+        assert_eq!(
+            run(text),
+            "A[3] B[1] C[3] D[2].\n\n[1]: https://b.com\n[2]: https://c.com"
+        );
+    }
+
+    #[test]
+    fn test_normalize_before_punctuation() {
+        let text = "One.[1][2] Two. [3] Three[4].";
+        assert_eq!(
+            run_normalize(
+                text,
+                CitationStyle::Numeric,
+                MarkerPlacement::BeforePunctuation
+            ),
+            "One[1][2]. Two[3]. Three[4]."
+        );
+    }
+
+    #[test]
+    fn test_normalize_after_punctuation() {
+        let text = "One[1][2]. Two.[3]";
+        assert_eq!(
+            run_normalize(
+                text,
+                CitationStyle::Numeric,
+                MarkerPlacement::AfterPunctuation
+            ),
+            "One.[1][2] Two.[3]"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::edits::Origins;
+
+/// A run of output text copied unchanged from the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PreservedSegment {
+    /// Byte range in the input
+    pub original: Range<usize>,
+    /// Byte range in the output
+    pub output: Range<usize>,
+}
+
+/// Maps offsets between a cleaned output and the input it was produced from
+///
+/// The output is a sequence of preserved input segments, separated only by
+/// text a [`Replacement`](crate::Replacement) put in place of a citation.
+/// Segments are sorted in both output and input order; with the default
+/// [`Replacement::Delete`](crate::Replacement::Delete) they cover the whole
+/// output without gaps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OffsetMap {
+    segments: Vec<PreservedSegment>,
+    input_len: usize,
+    output_len: usize,
+}
+
+impl OffsetMap {
+    pub(crate) fn new(origins: &Origins, input_len: usize, output_len: usize) -> Self {
+        let segments = origins
+            .segments()
+            .iter()
+            .map(|segment| PreservedSegment {
+                original: segment.input..segment.input + segment.output.len(),
+                output: segment.output.clone(),
+            })
+            .collect();
+        Self {
+            segments,
+            input_len,
+            output_len,
+        }
+    }
+
+    /// Preserved segments in order
+    pub fn segments(&self) -> &[PreservedSegment] {
+        &self.segments
+    }
+
+    /// Length of the input in bytes
+    pub fn input_len(&self) -> usize {
+        self.input_len
+    }
+
+    /// Length of the output in bytes
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Input offset of an output offset
+    ///
+    /// Offsets inside replacement text map to the start of the citation it
+    /// replaced. An offset at the end of the output maps to the end of the
+    /// last preserved segment. Offsets past the end are clamped.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let i = self.segments.partition_point(|s| s.output.end <= offset);
+        match self.segments.get(i) {
+            Some(segment) if segment.output.start <= offset => {
+                segment.original.start + (offset - segment.output.start)
+            }
+            _ => i
+                .checked_sub(1)
+                .map_or(0, |j| self.segments[j].original.end),
+        }
+    }
+
+    /// Input range covering an output range
+    ///
+    /// The result spans from the first to the last mapped byte, so it includes
+    /// any removed text in between. Replacement text at either end maps to
+    /// the whole citation it replaced.
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.original_offset(range.start);
+        if range.is_empty() {
+            return start..start;
+        }
+        let last = range.end - 1;
+        let i = self.segments.partition_point(|s| s.output.end <= last);
+        let end = match self.segments.get(i) {
+            Some(segment) if segment.output.start <= last => {
+                segment.original.start + (last - segment.output.start) + 1
+            }
+            Some(segment) => segment.original.start,
+            None => self.input_len,
+        };
+        start..end.max(start)
+    }
+
+    /// Output offset of an input offset, or `None` if that byte was removed
+    ///
+    /// The end of the input maps to the end of the output when the input's
+    /// last byte was preserved.
+    pub fn output_offset(&self, offset: usize) -> Option<usize> {
+        let i = self.segments.partition_point(|s| s.original.end <= offset);
+        match self.segments.get(i) {
+            Some(segment) if segment.original.start <= offset => {
+                Some(segment.output.start + (offset - segment.original.start))
+            }
+            None if offset == self.input_len
+                && self
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.original.end == offset) =>
+            {
+                Some(self.output_len)
+            }
+            _ => None,
+        }
+    }
+
+    /// Output range covering the preserved part of an input range
+    ///
+    /// Removed text at either end is skipped. If nothing in the range was
+    /// preserved, the result is an empty range where the text used to be.
+    pub fn output_range(&self, range: Range<usize>) -> Range<usize> {
+        let i = self
+            .segments
+            .partition_point(|s| s.original.end <= range.start);
+        let start = match self.segments.get(i) {
+            Some(segment) => {
+                segment.output.start + range.start.saturating_sub(segment.original.start)
+            }
+            None => self.output_len,
+        };
+
+        let j = self
+            .segments
+            .partition_point(|s| s.original.start < range.end);
+        let end = match j.checked_sub(1).map(|j| &self.segments[j]) {
+            Some(segment) if range.end <= segment.original.end => {
+                segment.output.start + (range.end - segment.original.start)
+            }
+            Some(segment) => segment.output.end,
+            None => 0,
+        };
+
+        start..end.max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CitationRemover, RemoverConfig, Replacement};
+
+    #[test]
+    fn test_segments_copy_input() {
+        let input =
+            "Intro[1]  text[^2].   \n\n\n\n## Sources\n[1]: https://a.com\n[^2]: Note\n\n# Outro\n";
+        let (output, map) = CitationRemover::new().remove_with_offset_map(input);
+        assert_eq!(output, "Intro text.\n\n# Outro\n");
+
+        for segment in map.segments() {
+            assert_eq!(
+                &input[segment.original.clone()],
+                &output[segment.output.clone()]
+            );
+        }
+        assert_eq!(
+            map.segments().last().map(|s| s.output.end),
+            Some(output.len())
+        );
+    }
+
+    #[test]
+    fn test_lookups_in_both_directions() {
+        let input = "Intro[1]  text.\n\n[1]: https://a.com\n\nOutro";
+        let (output, map) = CitationRemover::new().remove_with_offset_map(input);
+        assert_eq!(output, "Intro text.\n\nOutro");
+
+        let text = output.find("text").unwrap();
+        assert_eq!(map.original_offset(text), input.find("text").unwrap());
+        assert_eq!(map.original_range(0..10), 0..14);
+        assert_eq!(&input[map.original_range(text..text + 4)], "text");
+
+        assert_eq!(
+            map.output_offset(input.find("Outro").unwrap()),
+            output.find("Outro")
+        );
+        assert_eq!(map.output_offset(5), None);
+        assert_eq!(map.output_offset(input.len()), Some(output.len()));
+
+        let definition = input.find("[1]:").unwrap();
+        assert_eq!(map.output_range(definition..definition + 19), 13..13);
+        assert_eq!(map.output_range(0..input.len()), 0..output.len());
+    }
+
+    #[test]
+    fn test_replacement_text_maps_to_citation() {
+        let config = RemoverConfig {
+            replacement: Replacement::Text("†".to_string()),
+            ..Default::default()
+        };
+        let input = "Intro[1] text.";
+        let (output, map) = CitationRemover::with_config(config).remove_with_offset_map(input);
+        assert_eq!(output, "Intro† text.");
+
+        let segments: Vec<_> = map.segments().iter().map(|s| s.output.clone()).collect();
+        assert_eq!(segments, vec![0..5, 8..14]);
+        assert_eq!(map.original_offset(6), 5);
+        assert_eq!(map.original_range(5..8), 5..8);
+        assert_eq!(map.original_range(0..8), 0..8);
+        assert_eq!(map.output_offset(input.find("text").unwrap()), Some(9));
+    }
+}
@@ -1,5 +1,67 @@
 use once_cell::sync::Lazy;
-use regex::Regex;
+use std::ops::Range;
+
+use regex::{Captures, Regex, RegexSet};
+
+use crate::error::{RemoverError, Result};
+use crate::language::Language;
+
+/// Prefixes of named citations such as `[source:1]` recognised by default
+pub(crate) const NAMED_PREFIXES: &[&str] = &["source", "ref", "cite", "note", "fig", "table", "eq"];
+
+/// A user-supplied citation pattern
+///
+/// Registered through [`CitationRemoverBuilder`](crate::CitationRemoverBuilder).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// A regular expression in [`regex`](https://docs.rs/regex) syntax
+    Regex(String),
+    /// Text matched exactly
+    Literal(String),
+}
+
+impl Pattern {
+    /// A regular expression, checked when the remover is built, not here
+    pub fn regex(pattern: impl Into<String>) -> Self {
+        Self::Regex(pattern.into())
+    }
+
+    /// Text matched as is: regex metacharacters in it are escaped
+    pub fn literal(text: impl Into<String>) -> Self {
+        Self::Literal(text.into())
+    }
+
+    /// Regex source for this pattern, validated on its own
+    fn source(&self) -> Result<String> {
+        let source = match self {
+            Self::Regex(pattern) => pattern.clone(),
+            Self::Literal(text) => regex::escape(text),
+        };
+        let regex = Regex::new(&source)
+            .map_err(|e| RemoverError::InvalidPattern(format!("`{}`: {}", source, e)))?;
+        if regex.is_match("") {
+            return Err(RemoverError::InvalidPattern(format!(
+                "`{}` matches the empty string",
+                source
+            )));
+        }
+        Ok(source)
+    }
+}
+
+/// Extra patterns to compile on top of the built-in ones
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CustomPatterns {
+    pub inline: Vec<Pattern>,
+    pub definitions: Vec<Pattern>,
+    pub headers: Vec<Pattern>,
+}
+
+impl CustomPatterns {
+    pub fn is_empty(&self) -> bool {
+        self.inline.is_empty() && self.definitions.is_empty() && self.headers.is_empty()
+    }
+}
 
 /// Compiled regex patterns for citation removal
 ///
@@ -25,6 +87,14 @@ use regex::Regex;
 ///    - Used by: Custom academic formats, some documentation systems
 ///    - Example: "See figure[fig:1] for details."
 ///
+/// 4. **Lenticular citations**: `【1†source】`, `【4:0†report.pdf】`
+///    - Used by: ChatGPT (browsing and file search)
+///    - Example: "Revenue grew 40%【3†source】."
+///
+/// 5. **Full-width citations**: `［1］`
+///    - Used by: AI systems writing Chinese or Japanese
+///    - Example: "收入增长了40%［3］。"
+///
 /// ## Reference Definitions (Removed)
 ///
 /// 1. **Standard markdown reference links**: `[1]: https://example.com`
@@ -53,14 +123,25 @@ use regex::Regex;
 /// - HTML/XML tags: `<tag>content</tag>`
 ///
 /// Patterns use word boundaries and context to avoid false positives.
+#[derive(Debug, Clone)]
 pub(crate) struct Patterns {
     /// Matches ALL inline citation patterns:
     /// - Numeric: `[1]`, `[2]`, `[123]`
     /// - Footnote-style: `[^1]`, `[^note]`, `[^1_1]`, `[^1_2]`
     /// - Named: `[source:1]`, `[ref:2]`, `[cite:3]`
-    /// - Pandoc-style: `[@smith2004]`, `@citation`
+    /// - Lenticular: `【1†source】`
+    ///
+    /// - Full-width: `［1］`
+    ///
+    /// Capture groups: `footnote`, `numeric`, `prefix` + `named`, `lenticular`
+    /// and `fullwidth`
     pub inline_citations: Regex,
 
+    /// User-supplied inline citations, each with an optional `id` group
+    ///
+    /// Compiled one by one, so their capture group names can't clash.
+    pub custom_inline: Vec<Regex>,
+
     /// Matches ALL reference definition patterns at line start:
     /// - Standard: `[1]: url` or `[1] url`
     /// - Footnote: `[^1]: text` or `[^1_1]: url`
@@ -68,12 +149,18 @@ pub(crate) struct Patterns {
     /// - With any special characters in identifiers
     pub reference_definitions: Regex,
 
-    /// Matches reference section headers
+    /// Matches reference section headers, in any configured language
     pub reference_header: Regex,
 
+    /// Matches user-defined reference section headers
+    pub custom_headers: Option<RegexSet>,
+
     /// Matches full reference entries (fallback for complex formats)
     pub reference_entry: Regex,
 
+    /// Matches whole lines that start a user-defined reference definition
+    pub custom_definitions: Option<RegexSet>,
+
     /// Whitespace and formatting cleanup patterns
     pub multiple_whitespace: Regex,
     pub excessive_newlines: Regex,
@@ -81,33 +168,8 @@ pub(crate) struct Patterns {
 
 /// Lazily compiled patterns (compiled once, used many times)
 pub(crate) static PATTERNS: Lazy<Patterns> = Lazy::new(|| Patterns {
-    // INLINE CITATIONS - Comprehensive pattern matching ALL formats
-    // This pattern matches:
-    // 1. Numeric citations: [1], [2], [123]
-    // 2. Footnote citations: [^1], [^note], [^1_1], [^section_note]
-    // 3. Named citations: [source:1], [ref:2], [cite:3], [note:4]
-    // 4. Pandoc citations: [@smith2004], [@doe99], @citation_key
-    //
-    // The pattern is intentionally broad to catch variations while avoiding:
-    // - Regular markdown links: [text](url) - these have text before the bracket
-    // - Code references: array[1] - these don't have opening bracket at word boundary
-    inline_citations: Regex::new(
-        r"(?x)
-        # Match any of these inline citation formats:
-        (?:
-            # Footnote-style with caret: [^identifier]
-            # Matches: [^1], [^note], [^1_1], [^section_note], etc.
-            \[\^[a-zA-Z0-9_\-]+\]
-            |
-            # Numeric citations: [1], [2], [123]
-            \[\d+\]
-            |
-            # Named citations: [source:1], [ref:2], [cite:3], [note:4]
-            \[(?:source|ref|cite|note|fig|table|eq):[a-zA-Z0-9_\-]+\]
-        )
-        ",
-    )
-    .unwrap(),
+    inline_citations: inline_citations(NAMED_PREFIXES),
+    custom_inline: Vec::new(),
 
     // REFERENCE DEFINITIONS - Comprehensive pattern for all reference formats
     // This pattern matches lines that START with citation markers followed by content:
@@ -138,11 +200,8 @@ pub(crate) static PATTERNS: Lazy<Patterns> = Lazy::new(|| Patterns {
     )
     .unwrap(),
 
-    // Reference section headers - unchanged, already comprehensive
-    reference_header: Regex::new(
-        r"(?m)^#{1,6}\s*(?:References?|Citations?|Sources?|Bibliography|Notes?)\s*$",
-    )
-    .unwrap(),
+    // Reference section headers: `## References`, `# Sources`, ...
+    reference_header: reference_header(Language::English.header_keywords()),
 
     // Full reference entries as fallback: [1] Author, A. (2024)...
     // This catches reference-like lines that might not match other patterns
@@ -160,12 +219,191 @@ pub(crate) static PATTERNS: Lazy<Patterns> = Lazy::new(|| Patterns {
     // Whitespace cleanup patterns - unchanged
     multiple_whitespace: Regex::new(r" {2,}").unwrap(),
     excessive_newlines: Regex::new(r"\n{3,}").unwrap(),
+    custom_headers: None,
+    custom_definitions: None,
 });
 
 impl Patterns {
     pub fn get() -> &'static Patterns {
         &PATTERNS
     }
+
+    /// The built-in patterns with other named-citation prefixes and
+    /// reference header keywords, matched literally
+    pub fn with_vocabulary(prefixes: &[impl AsRef<str>], keywords: &[impl AsRef<str>]) -> Patterns {
+        Patterns {
+            inline_citations: inline_citations(prefixes),
+            reference_header: reference_header(keywords),
+            ..PATTERNS.clone()
+        }
+    }
+
+    /// These patterns extended with `custom`
+    ///
+    /// Each custom pattern is validated and compiled on its own, so errors name
+    /// the offending pattern and patterns may reuse group names like `id`.
+    pub fn with_custom(mut self, custom: &CustomPatterns) -> Result<Patterns> {
+        for pattern in &custom.inline {
+            self.custom_inline.push(compile(&pattern.source()?)?);
+        }
+        if let Some(headers) = compile_set(&custom.headers, |header| {
+            format!(r"(?m:^#{{1,6}}\s*(?:{})\s*$)", header)
+        })? {
+            self.custom_headers = Some(headers);
+        }
+        if let Some(definitions) = compile_set(&custom.definitions, |definition| {
+            format!("^(?:{})", definition)
+        })? {
+            self.custom_definitions = Some(definitions);
+        }
+        Ok(self)
+    }
+
+    /// Whether user-supplied inline patterns are compiled in
+    pub fn has_custom_inline(&self) -> bool {
+        !self.custom_inline.is_empty()
+    }
+
+    /// Whether `line` is a built-in or user-defined reference section header
+    pub fn is_reference_header(&self, line: &str) -> bool {
+        self.reference_header.is_match(line)
+            || self
+                .custom_headers
+                .as_ref()
+                .is_some_and(|headers| headers.is_match(line))
+    }
+
+    /// Inline citations in `text`, leftmost first
+    ///
+    /// Where several patterns match at the same position, the built-in ones
+    /// win, then the custom patterns in the order they were added.
+    pub fn inline_matches<'t>(&self, text: &'t str) -> Vec<InlineMatch<'t>> {
+        let regexes: Vec<&Regex> = std::iter::once(&self.inline_citations)
+            .chain(&self.custom_inline)
+            .collect();
+        let mut next: Vec<Option<Captures>> = regexes.iter().map(|r| r.captures(text)).collect();
+        let mut matches = Vec::new();
+
+        while let Some((first, _)) = next
+            .iter()
+            .enumerate()
+            .filter_map(|(i, caps)| Some((i, span(caps.as_ref()?).start)))
+            .min_by_key(|&(i, start)| (start, i))
+        {
+            let Some(captures) = next[first].take() else {
+                break;
+            };
+            let end = span(&captures).end;
+            matches.push(InlineMatch {
+                captures,
+                custom: first > 0,
+            });
+            // Look again wherever the next match overlaps the one just taken
+            for (i, caps) in next.iter_mut().enumerate() {
+                if caps
+                    .as_ref()
+                    .map_or(i == first, |caps| span(caps).start < end)
+                {
+                    *caps = regexes[i].captures_at(text, end);
+                }
+            }
+        }
+        matches
+    }
+}
+
+/// A match of an inline citation pattern
+pub(crate) struct InlineMatch<'t> {
+    pub captures: Captures<'t>,
+    /// Whether a user-supplied pattern matched
+    pub custom: bool,
+}
+
+/// Byte range of a whole match
+fn span(caps: &Captures) -> Range<usize> {
+    caps.get(0).map_or(0..0, |m| m.range())
+}
+
+/// Inline citation pattern accepting the given named-citation prefixes
+fn inline_citations(prefixes: &[impl AsRef<str>]) -> Regex {
+    // INLINE CITATIONS - Comprehensive pattern matching ALL formats
+    // This pattern matches:
+    // 1. Numeric citations: [1], [2], [123]
+    // 2. Footnote citations: [^1], [^note], [^1_1], [^section_note]
+    // 3. Named citations: [source:1], [ref:2], [cite:3], [note:4], with configurable prefixes
+    // 4. Lenticular citations: 【1†source】, 【4:0†report.pdf】
+    // 5. Full-width numeric citations: ［1］
+    //
+    // Each format captures its identifier in a named group so the same pattern
+    // can be used to parse markers, not just to remove them.
+    //
+    // The pattern is intentionally broad to catch variations while avoiding:
+    // - Regular markdown links: [text](url) - these have text before the bracket
+    // - Code references: array[1] - these don't have opening bracket at word boundary
+    Regex::new(&format!(
+        r"(?x)
+        # Match any of these inline citation formats:
+        (?:
+            # Footnote-style with caret: [^identifier]
+            # Matches: [^1], [^note], [^1_1], [^section_note], etc.
+            \[\^(?P<footnote>[a-zA-Z0-9_\-]+)\]
+            |
+            # Numeric citations: [1], [2], [123]
+            \[(?P<numeric>\d+)\]
+            |
+            # Named citations: [source:1], [ref:2], [cite:3], [cite: 5]
+            \[(?P<prefix>(?-x:{prefixes})):\x20?(?P<named>[a-zA-Z0-9_\-]+)\]
+            |
+            # ChatGPT lenticular citations: 【1†source】, 【4:0†report.pdf】
+            【(?P<lenticular>\d+(?::\d+)?)†[^】\n]*】
+            |
+            # Full-width numeric citations in CJK text: ［1］
+            ［(?P<fullwidth>\d+)］
+        )
+        ",
+        prefixes = alternation(prefixes)
+    ))
+    .unwrap()
+}
+
+/// Reference header pattern accepting the given heading keywords
+fn reference_header(keywords: &[impl AsRef<str>]) -> Regex {
+    Regex::new(&format!(
+        r"(?m)^#{{1,6}}\s*(?:{})\s*$",
+        alternation(keywords)
+    ))
+    .unwrap()
+}
+
+/// Escaped alternation of literals; never matches if there are none
+fn alternation(literals: &[impl AsRef<str>]) -> String {
+    if literals.is_empty() {
+        return r"[^\s\S]".to_string();
+    }
+    literals
+        .iter()
+        .map(|literal| regex::escape(literal.as_ref()))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Compile each pattern, wrapped by `wrap`, into one set, or `None` if there
+/// are none
+fn compile_set(patterns: &[Pattern], wrap: impl Fn(String) -> String) -> Result<Option<RegexSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let sources = patterns
+        .iter()
+        .map(|p| p.source().map(&wrap))
+        .collect::<Result<Vec<_>>>()?;
+    RegexSet::new(sources)
+        .map(Some)
+        .map_err(|e| RemoverError::InvalidPattern(e.to_string()))
+}
+
+fn compile(source: &str) -> Result<Regex> {
+    Regex::new(source).map_err(|e| RemoverError::InvalidPattern(e.to_string()))
 }
 
 #[cfg(test)]
@@ -206,9 +444,21 @@ mod tests {
         assert!(patterns.inline_citations.is_match("[cite:3]"));
         assert!(patterns.inline_citations.is_match("[note:4]"));
         assert!(patterns.inline_citations.is_match("[fig:1]"));
+        assert!(patterns.inline_citations.is_match("[cite: 5]"));
         assert!(!patterns.inline_citations.is_match("[other:1]"));
     }
 
+    #[test]
+    fn test_inline_citations_lenticular() {
+        let patterns = Patterns::get();
+        assert!(patterns.inline_citations.is_match("【1†source】"));
+        assert!(patterns.inline_citations.is_match("【4:0†report.pdf】"));
+        assert!(!patterns.inline_citations.is_match("【note】"));
+
+        let caps = patterns.inline_citations.captures("【12†source】").unwrap();
+        assert_eq!(&caps["lenticular"], "12");
+    }
+
     // Pandoc citation support temporarily disabled due to regex complexity
     // Will be added in a future update
     // #[test]
@@ -283,6 +533,69 @@ mod tests {
         assert!(!patterns.reference_header.is_match("## Other Section"));
     }
 
+    #[test]
+    fn test_custom_patterns_extend_defaults() {
+        let custom = CustomPatterns {
+            inline: vec![
+                Pattern::regex(r"\{\{src:(?P<id>\w+)\}\}"),
+                Pattern::literal("(*)"),
+            ],
+            definitions: vec![Pattern::regex(r"Source \d+:")],
+            headers: vec![Pattern::literal("Quellen")],
+        };
+        let patterns = PATTERNS.clone().with_custom(&custom).unwrap();
+        assert!(patterns.has_custom_inline());
+        assert!(!Patterns::get().has_custom_inline());
+
+        let matches = patterns.inline_matches("see {{src:a1}}, [1] and (*)");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(&matches[0].captures["id"], "a1");
+        assert!(matches[0].custom && !matches[1].custom && matches[2].custom);
+
+        assert!(patterns.is_reference_header("## Quellen"));
+        assert!(patterns.is_reference_header("## References"));
+        let definitions = patterns.custom_definitions.unwrap();
+        assert!(definitions.is_match("Source 1: https://a.com"));
+        assert!(!definitions.is_match("See Source 1: here"));
+    }
+
+    #[test]
+    fn test_custom_patterns_may_share_group_names() {
+        let custom = CustomPatterns {
+            inline: vec![
+                Pattern::regex(r"\{\{src:(?P<id>\w+)\}\}"),
+                Pattern::regex(r"<<cit:(?P<id>\w+)>>"),
+                Pattern::regex(r"\[(?P<numeric>\d+)\]"),
+            ],
+            headers: vec![
+                Pattern::regex("(?P<h>Quellen)"),
+                Pattern::regex("(?P<h>Fuentes)"),
+            ],
+            ..Default::default()
+        };
+        let patterns = PATTERNS.clone().with_custom(&custom).unwrap();
+        let matches = patterns.inline_matches("A<<cit:x>> B{{src:y}} C[2]");
+        let ids: Vec<_> = matches[..2].iter().map(|m| &m.captures["id"]).collect();
+        assert_eq!(ids, ["x", "y"]);
+        // The built-in pattern wins where both match
+        assert!(!matches[2].custom);
+        assert!(patterns.is_reference_header("# Fuentes"));
+    }
+
+    #[test]
+    fn test_custom_patterns_are_validated() {
+        let invalid = |pattern| CustomPatterns {
+            inline: vec![pattern],
+            ..Default::default()
+        };
+        for pattern in [Pattern::regex("(unclosed"), Pattern::regex("a*")] {
+            assert!(matches!(
+                PATTERNS.clone().with_custom(&invalid(pattern)),
+                Err(RemoverError::InvalidPattern(_))
+            ));
+        }
+    }
+
     #[test]
     fn test_reference_entry_pattern() {
         let patterns = Patterns::get();
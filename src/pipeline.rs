@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use once_cell::unsync::OnceCell;
+
+use crate::analysis::Source;
+use crate::blocks;
+use crate::citations::Marker;
+use crate::config::RemoverConfig;
+use crate::remover::CitationRemover;
+use crate::report::RuleId;
+
+/// One step of a [`Pipeline`]
+///
+/// A stage looks at the current text, which earlier stages have already
+/// edited, and returns the edits it wants to make. The pipeline applies them
+/// and keeps reports, offset maps and sidecars in step, so a stage never
+/// edits the text itself.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_ai_cite_remove::{
+///     BuiltinStage, CitationRemover, Pipeline, RuleId, Stage, StageContext, StageEdit,
+/// };
+///
+/// /// Strips `{{src:…}}` markers outside code
+/// struct StripSrc;
+///
+/// impl Stage for StripSrc {
+///     fn name(&self) -> &str {
+///         "strip-src"
+///     }
+///
+///     fn edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+///         let mut edits = Vec::new();
+///         let mut from = 0;
+///         while let Some(start) = text[from..].find("{{src:").map(|i| from + i) {
+///             let Some(end) = text[start..].find("}}").map(|i| start + i + 2) else {
+///                 break;
+///             };
+///             if !context.is_protected(start..end) {
+///                 edits.push(StageEdit::delete(start..end, RuleId::custom("strip-src")));
+///             }
+///             from = end;
+///         }
+///         edits
+///     }
+/// }
+///
+/// let mut pipeline = Pipeline::standard();
+/// pipeline.insert_after(BuiltinStage::References.name(), StripSrc);
+///
+/// let remover = CitationRemover::builder().pipeline(pipeline).build()?;
+/// let output = remover.remove("Fact{{src:a}}[1] and `{{src:b}}`.\n\n[1]: https://example.com");
+/// assert_eq!(output, "Fact and `{{src:b}}`.");
+/// # Ok::<(), markdown_ai_cite_remove::RemoverError>(())
+/// ```
+pub trait Stage: Send + Sync {
+    /// Name used to find the stage in a [`Pipeline`]
+    fn name(&self) -> &str;
+
+    /// Edits to make to `text`
+    ///
+    /// Edits may come in any order. Edits overlapping an earlier one, or with
+    /// ranges out of bounds or not on character boundaries, are ignored.
+    fn edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit>;
+}
+
+/// Replace `range` of the current text with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageEdit {
+    pub range: Range<usize>,
+    /// Text put in place of the range, empty for deletions
+    pub replacement: String,
+    /// Rule the edit is reported under
+    pub rule: RuleId,
+    /// Later piece of a block already reported, not counted again
+    pub(crate) continues: bool,
+}
+
+impl StageEdit {
+    /// Delete `range`
+    pub fn delete(range: Range<usize>, rule: RuleId) -> Self {
+        Self::replace(range, String::new(), rule)
+    }
+
+    /// Replace `range` with `replacement`
+    pub fn replace(range: Range<usize>, replacement: impl Into<String>, rule: RuleId) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+            rule,
+            continues: false,
+        }
+    }
+}
+
+/// What a [`Stage`] may need to know besides the current text
+pub struct StageContext<'a> {
+    pub(crate) remover: &'a CitationRemover,
+    sources: &'a HashMap<String, Source>,
+    text: &'a str,
+    protected: OnceCell<Vec<Range<usize>>>,
+    /// Whether the input of the pipeline ended with a line break
+    pub(crate) final_newline: bool,
+}
+
+impl<'a> StageContext<'a> {
+    pub(crate) fn new(
+        remover: &'a CitationRemover,
+        sources: &'a HashMap<String, Source>,
+        text: &'a str,
+        final_newline: bool,
+    ) -> Self {
+        Self {
+            remover,
+            sources,
+            text,
+            protected: OnceCell::new(),
+            final_newline,
+        }
+    }
+
+    /// Configuration of the remover running the pipeline
+    pub fn config(&self) -> &RemoverConfig {
+        self.remover.config()
+    }
+
+    /// Source defined in the input under `label`
+    ///
+    /// Sources are looked up for custom pipelines and when the configured
+    /// [`Replacement`](crate::Replacement) needs them; otherwise this
+    /// returns `None`.
+    pub fn source(&self, label: &str) -> Option<&Source> {
+        self.sources.get(label)
+    }
+
+    /// The source an inline marker refers to
+    pub(crate) fn source_of(&self, marker: &Marker) -> Option<&Source> {
+        marker
+            .candidate_labels()
+            .iter()
+            .find_map(|label| self.sources.get(label))
+    }
+
+    /// Regions of the current text that are not prose: fenced code blocks,
+    /// inline code spans and HTML comments, sorted by start
+    pub fn protected_ranges(&self) -> &[Range<usize>] {
+        self.protected
+            .get_or_init(|| blocks::protected_ranges(self.text))
+    }
+
+    /// Whether `range` overlaps a protected region
+    pub fn is_protected(&self, range: Range<usize>) -> bool {
+        let ranges = self.protected_ranges();
+        let i = ranges.partition_point(|r| r.end <= range.start);
+        ranges
+            .get(i)
+            .is_some_and(|r| r.start < range.end.max(range.start + 1))
+    }
+}
+
+/// The built-in stages, in their default order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BuiltinStage {
+    /// Vendor-specific leftovers listed in `artifacts`
+    Artifacts,
+    /// Reference definitions, entries and sections, as enabled by
+    /// `remove_reference_links`, `remove_reference_entries` and
+    /// `remove_reference_headers`
+    References,
+    /// Inline citation markers, replaced by the configured
+    /// [`Replacement`](crate::Replacement)
+    InlineCitations,
+    /// Runs of spaces collapsed to one
+    Whitespace,
+    /// Three or more line breaks reduced to a blank line
+    BlankLines,
+    /// Trailing whitespace on every line, and blank lines at the end; the
+    /// text ends with a line break if the input did
+    TrimLines,
+}
+
+impl BuiltinStage {
+    /// Every built-in stage, in the default order
+    pub const ALL: &'static [BuiltinStage] = &[
+        Self::Artifacts,
+        Self::References,
+        Self::InlineCitations,
+        Self::Whitespace,
+        Self::BlankLines,
+        Self::TrimLines,
+    ];
+
+    /// Stable name, e.g. `inline-citations`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Artifacts => "artifacts",
+            Self::References => "references",
+            Self::InlineCitations => "inline-citations",
+            Self::Whitespace => "whitespace",
+            Self::BlankLines => "blank-lines",
+            Self::TrimLines => "trim-lines",
+        }
+    }
+
+    /// Whether `config` enables this stage
+    pub(crate) fn enabled(self, config: &RemoverConfig) -> bool {
+        match self {
+            Self::Artifacts => !config.artifacts.is_empty(),
+            Self::References => {
+                config.remove_reference_links
+                    || config.remove_reference_entries
+                    || config.remove_reference_headers
+            }
+            Self::InlineCitations => config.remove_inline_citations,
+            Self::Whitespace => config.normalize_whitespace,
+            Self::BlankLines => config.remove_blank_lines,
+            Self::TrimLines => config.trim_lines,
+        }
+    }
+}
+
+impl Stage for BuiltinStage {
+    fn name(&self) -> &str {
+        BuiltinStage::name(*self)
+    }
+
+    fn edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+        let remover = context.remover;
+        match self {
+            Self::Artifacts => remover.artifact_deletions(text, context),
+            Self::References => remover.reference_deletions(text),
+            Self::InlineCitations => remover.inline_edits(text, context),
+            Self::Whitespace => remover.whitespace_deletions(text),
+            Self::BlankLines => remover.blank_line_deletions(text),
+            Self::TrimLines => remover.trailing_whitespace_deletions(text, context.final_newline),
+        }
+    }
+}
+
+/// A [`Stage`] made from a name and a closure
+pub struct FnStage<F> {
+    name: String,
+    f: F,
+}
+
+impl<F> FnStage<F>
+where
+    F: Fn(&str, &StageContext) -> Vec<StageEdit> + Send + Sync,
+{
+    pub fn new(name: impl Into<String>, f: F) -> Self {
+        Self {
+            name: name.into(),
+            f,
+        }
+    }
+}
+
+impl<F> Stage for FnStage<F>
+where
+    F: Fn(&str, &StageContext) -> Vec<StageEdit> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+        (self.f)(text, context)
+    }
+}
+
+/// An ordered list of stages run by [`CitationRemover`]
+///
+/// Without a pipeline, a remover runs the built-in stages its
+/// [`RemoverConfig`] enables; [`Pipeline::from_config`] builds the same list
+/// as a starting point. Stages are looked up by [`Stage::name`].
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Arc<dyn Stage>>,
+}
+
+impl Pipeline {
+    /// A pipeline without stages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every built-in stage in the default order
+    pub fn standard() -> Self {
+        Self::builtin(BuiltinStage::ALL.iter().copied())
+    }
+
+    /// The built-in stages `config` enables, in the default order
+    pub fn from_config(config: &RemoverConfig) -> Self {
+        Self::builtin(
+            BuiltinStage::ALL
+                .iter()
+                .copied()
+                .filter(|stage| stage.enabled(config)),
+        )
+    }
+
+    fn builtin(stages: impl Iterator<Item = BuiltinStage>) -> Self {
+        Self {
+            stages: stages
+                .map(|stage| Arc::new(stage) as Arc<dyn Stage>)
+                .collect(),
+        }
+    }
+
+    /// Stage names in order
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Whether a stage named `name` is in the pipeline
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Add a stage at the end
+    pub fn push(&mut self, stage: impl Stage + 'static) -> &mut Self {
+        self.stages.push(Arc::new(stage));
+        self
+    }
+
+    /// Insert a stage before the one named `name`, or at the end if there is none
+    pub fn insert_before(&mut self, name: &str, stage: impl Stage + 'static) -> &mut Self {
+        let i = self.position(name).unwrap_or(self.stages.len());
+        self.stages.insert(i, Arc::new(stage));
+        self
+    }
+
+    /// Insert a stage after the one named `name`, or at the end if there is none
+    pub fn insert_after(&mut self, name: &str, stage: impl Stage + 'static) -> &mut Self {
+        let i = self.position(name).map_or(self.stages.len(), |i| i + 1);
+        self.stages.insert(i, Arc::new(stage));
+        self
+    }
+
+    /// Replace the stage named `name`; returns whether there was one
+    pub fn replace(&mut self, name: &str, stage: impl Stage + 'static) -> bool {
+        match self.position(name) {
+            Some(i) => {
+                self.stages[i] = Arc::new(stage);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the stage named `name`; returns whether there was one
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(i) => {
+                self.stages.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the stage named `name` to just before the one named `before`;
+    /// returns whether both were found
+    pub fn move_before(&mut self, name: &str, before: &str) -> bool {
+        let (Some(from), Some(_)) = (self.position(name), self.position(before)) else {
+            return false;
+        };
+        let stage = self.stages.remove(from);
+        let to = self.position(before).unwrap_or(self.stages.len());
+        self.stages.insert(to, stage);
+        true
+    }
+
+    pub(crate) fn stages(&self) -> &[Arc<dyn Stage>] {
+        &self.stages
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.stages.iter().position(|stage| stage.name() == name)
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Sort edits and drop those that are out of bounds, split a character,
+/// overlap an earlier edit or change nothing
+pub(crate) fn sanitize(text: &str, mut edits: Vec<StageEdit>) -> Vec<StageEdit> {
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    let mut last = 0;
+    edits.retain(|edit| {
+        let range = &edit.range;
+        let valid = last <= range.start
+            && range.start <= range.end
+            && range.end <= text.len()
+            && text.is_char_boundary(range.start)
+            && text.is_char_boundary(range.end)
+            && !(range.is_empty() && edit.replacement.is_empty());
+        if valid {
+            last = range.end;
+        }
+        valid
+    });
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake(name: &'static str) -> FnStage<impl Fn(&str, &StageContext) -> Vec<StageEdit>> {
+        FnStage::new(name, |_: &str, _: &StageContext| Vec::new())
+    }
+
+    #[test]
+    fn test_pipeline_editing() {
+        let mut pipeline = Pipeline::standard();
+        pipeline.insert_after("references", fake("strip-src"));
+        pipeline.insert_before("references", fake("first"));
+        assert!(pipeline.remove("blank-lines"));
+        assert!(!pipeline.remove("missing"));
+        assert!(pipeline.move_before("trim-lines", "whitespace"));
+        assert!(pipeline.replace("first", fake("renamed")));
+        assert_eq!(
+            pipeline.names(),
+            vec![
+                "artifacts",
+                "renamed",
+                "references",
+                "strip-src",
+                "inline-citations",
+                "trim-lines",
+                "whitespace"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_config_matches_enabled_stages() {
+        let pipeline = Pipeline::from_config(&RemoverConfig::inline_only());
+        assert_eq!(
+            pipeline.names(),
+            vec!["inline-citations", "whitespace", "trim-lines"]
+        );
+
+        let config = RemoverConfig::for_provider(crate::Provider::Gemini);
+        assert_eq!(Pipeline::from_config(&config).names()[0], "artifacts");
+    }
+
+    #[test]
+    fn test_sanitize_drops_invalid_edits() {
+        let rule = RuleId::custom("test");
+        let edits = vec![
+            StageEdit::delete(4..6, rule.clone()),
+            StageEdit::delete(0..2, rule.clone()),
+            StageEdit::delete(1..3, rule.clone()),
+            StageEdit::delete(5..40, rule.clone()),
+            StageEdit::delete(3..3, rule.clone()),
+            StageEdit::replace(3..3, "x", rule.clone()),
+            StageEdit::delete(7..8, rule),
+        ];
+        let kept: Vec<_> = sanitize("abcdéf", edits)
+            .into_iter()
+            .map(|e| e.range)
+            .collect();
+        assert_eq!(kept, vec![0..2, 3..3, 4..6]);
+    }
+}
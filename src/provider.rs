@@ -0,0 +1,90 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::artifacts::Artifact;
+use crate::detect::{detect_source, AUTO_THRESHOLD};
+use crate::names::named_enum;
+
+/// AI product a document was copied or exported from
+///
+/// [`RemoverConfig::for_provider`](crate::RemoverConfig::for_provider) turns
+/// on the citation prefixes and artifact cleanups the product produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Provider {
+    /// ChatGPT answers with web search: `【1†source】`, `citeturn0search0`,
+    /// `:contentReference[oaicite:0]{index=0}` and `?utm_source=chatgpt.com` links
+    #[cfg_attr(feature = "serde", serde(rename = "chatgpt"))]
+    ChatGpt,
+    /// ChatGPT Deep Research reports, with the same artifacts as ChatGPT
+    #[cfg_attr(feature = "serde", serde(rename = "chatgpt-deep-research"))]
+    ChatGptDeepResearch,
+    /// Perplexity answers copied from the web app: `[1]` and `[1](url)` lists
+    Perplexity,
+    /// Perplexity markdown exports: `[^1_1]` footnotes, logo and `⁂` separators
+    PerplexityExport,
+    /// Claude answers: `[1]` markers and `Sources` sections
+    Claude,
+    /// Gemini answers: `[cite_start]` and `[cite: 1, 2]`
+    Gemini,
+    /// Microsoft Copilot answers: `[^1^]` and `Source: Conversation with Copilot` lists
+    Copilot,
+    /// DeepSeek answers: `[citation:3]`
+    #[cfg_attr(feature = "serde", serde(rename = "deepseek"))]
+    DeepSeek,
+}
+
+named_enum! {
+    Provider, "provider" {
+        ChatGpt => "chatgpt",
+        ChatGptDeepResearch => "chatgpt-deep-research",
+        Perplexity => "perplexity",
+        PerplexityExport => "perplexity-export",
+        Claude => "claude",
+        Gemini => "gemini",
+        Copilot => "copilot",
+        DeepSeek => "deepseek",
+    }
+}
+
+impl Provider {
+    /// The provider that most likely produced `markdown`, if the evidence is
+    /// strong enough (a confidence of at least 0.5)
+    ///
+    /// See [`detect_source`] for every candidate and its score.
+    pub fn detect(markdown: &str) -> Option<Self> {
+        detect_source(markdown)
+            .into_iter()
+            .next()
+            .filter(|detection| detection.confidence >= AUTO_THRESHOLD)
+            .map(|detection| detection.provider)
+    }
+
+    /// Artifact cleanups for documents from this provider
+    pub fn artifacts(self) -> &'static [Artifact] {
+        match self {
+            Self::ChatGpt | Self::ChatGptDeepResearch => &[
+                Artifact::ContentReference,
+                Artifact::CiteTurn,
+                Artifact::TrackingParams,
+            ],
+            Self::PerplexityExport => &[Artifact::PerplexityChrome],
+            Self::Gemini => &[Artifact::CiteStart],
+            Self::Copilot => &[Artifact::CopilotFootnotes, Artifact::CopilotSources],
+            Self::Perplexity | Self::Claude | Self::DeepSeek => &[],
+        }
+    }
+
+    /// Named-citation prefixes used besides the built-in ones
+    pub fn named_prefixes(self) -> &'static [&'static str] {
+        match self {
+            Self::DeepSeek => &["citation"],
+            _ => &[],
+        }
+    }
+}
@@ -1,134 +1,673 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::analysis::{self, CitationAnalysis, Source};
+use crate::blocks::{self, Block, BlockKind};
+use crate::builder::CitationRemoverBuilder;
+use crate::citations::{self, CitationKind};
 use crate::config::RemoverConfig;
+use crate::convert::{self, CitationFormat};
+use crate::edits;
+use crate::error::Result;
+use crate::normalize::{self, NormalizeOptions};
+use crate::offset_map::OffsetMap;
 use crate::patterns::Patterns;
+use crate::pipeline::{self, BuiltinStage, Pipeline, Stage, StageContext, StageEdit};
+use crate::replacement::{CitationMatch, Replacement};
+use crate::report::{Recorder, RemovalReport, RuleId};
+use crate::sidecar::CitationSidecar;
+use crate::text_edit::{self, OffsetEncoding, TextEdit};
 
 /// Main citation remover
 pub struct CitationRemover {
     config: RemoverConfig,
-    patterns: &'static Patterns,
+    /// The shared built-in patterns, unless custom patterns were registered
+    patterns: Cow<'static, Patterns>,
+    /// Stages to run, `None` for the built-in stages the configuration enables
+    pipeline: Option<Pipeline>,
 }
 
 impl CitationRemover {
     /// Create new remover with default configuration
     pub fn new() -> Self {
-        Self {
-            config: RemoverConfig::default(),
-            patterns: Patterns::get(),
-        }
+        Self::with_config(RemoverConfig::default())
     }
 
     /// Create remover with custom configuration
     pub fn with_config(config: RemoverConfig) -> Self {
+        let patterns = if config.has_default_vocabulary() {
+            Cow::Borrowed(Patterns::get())
+        } else {
+            Cow::Owned(Patterns::with_vocabulary(
+                &config.named_prefixes,
+                &config.header_keywords,
+            ))
+        };
         Self {
             config,
-            patterns: Patterns::get(),
+            patterns,
+            pipeline: None,
         }
     }
 
+    /// Start building a remover with extra citation patterns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, Pattern};
+    ///
+    /// let remover = CitationRemover::builder()
+    ///     .inline_pattern(Pattern::regex(r"\{\{src:\d+\}\}"))
+    ///     .header_pattern(Pattern::literal("Further Reading"))
+    ///     .build()?;
+    /// let output = remover.remove("Fact{{src:1}}.\n\n## Further Reading\n- Book");
+    /// assert_eq!(output, "Fact.");
+    /// # Ok::<(), markdown_ai_cite_remove::RemoverError>(())
+    /// ```
+    pub fn builder() -> CitationRemoverBuilder {
+        CitationRemoverBuilder::new()
+    }
+
+    pub(crate) fn with_parts(
+        config: RemoverConfig,
+        patterns: Option<Patterns>,
+        pipeline: Option<Pipeline>,
+    ) -> Self {
+        let mut remover = match patterns {
+            Some(patterns) => Self {
+                config,
+                patterns: Cow::Owned(patterns),
+                pipeline: None,
+            },
+            None => Self::with_config(config),
+        };
+        remover.pipeline = pipeline;
+        remover
+    }
+
+    /// The configuration this remover was built with
+    pub fn config(&self) -> &RemoverConfig {
+        &self.config
+    }
+
+    /// The stages [`remove`](Self::remove) runs, in order
+    pub fn pipeline(&self) -> Pipeline {
+        self.pipeline
+            .clone()
+            .unwrap_or_else(|| Pipeline::from_config(&self.config))
+    }
+
     /// Remove citations from markdown string
     pub fn remove(&self, markdown: &str) -> String {
-        let mut result = markdown.to_string();
+        let sources = self.sources(markdown);
+        let final_newline = markdown.ends_with('\n');
+        self.run(
+            markdown.to_string(),
+            &self.stages(),
+            &sources,
+            final_newline,
+            None,
+        )
+    }
 
-        // Step 1: Remove reference sections FIRST (before inline citations)
-        // This is important because inline citation removal would break reference link patterns
-        if self.config.remove_reference_links
-            || self.config.remove_reference_entries
-            || self.config.remove_reference_headers
-        {
-            result = self.remove_reference_sections(&result);
-        }
+    /// Remove citations and report what was removed
+    ///
+    /// The report lists every removal with the rule that made it, its byte
+    /// range, line and column in the input and the removed text, plus totals
+    /// per category. The returned text is the same as [`remove`](Self::remove).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, RuleId};
+    ///
+    /// let remover = CitationRemover::new();
+    /// let (output, report) = remover.remove_with_report("Fact[1].\n\n[1]: https://example.com");
+    /// assert_eq!(output.trim(), "Fact.");
+    /// assert_eq!((report.counts.inline, report.counts.definitions), (1, 1));
+    ///
+    /// let marker = report.by_rule(RuleId::InlineNumeric).next().unwrap();
+    /// assert_eq!((marker.range.clone(), marker.line, marker.column), (4..7, 1, 5));
+    /// assert_eq!(marker.text, "[1]");
+    /// ```
+    pub fn remove_with_report(&self, markdown: &str) -> (String, RemovalReport) {
+        let (output, report, _) = self.remove_recorded(markdown);
+        (output, report)
+    }
+
+    /// Remove citations and map offsets between the output and the input
+    ///
+    /// The map lists the input segments preserved in the output and looks up
+    /// offsets in both directions, across every pipeline stage including
+    /// whitespace normalization and line trimming. The returned text is the
+    /// same as [`remove`](Self::remove).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::CitationRemover;
+    ///
+    /// let input = "Rust[1]  is fast.\n\n[1]: https://rust-lang.org";
+    /// let (output, map) = CitationRemover::new().remove_with_offset_map(input);
+    /// assert_eq!(output, "Rust is fast.");
+    ///
+    /// // "fast" in the output, back in the input
+    /// let range = map.original_range(8..12);
+    /// assert_eq!(&input[range], "fast");
+    ///
+    /// // The marker was removed, so it has no output offset
+    /// assert_eq!(map.output_offset(4), None);
+    /// ```
+    pub fn remove_with_offset_map(&self, markdown: &str) -> (String, OffsetMap) {
+        let (output, _, map) = self.remove_recorded(markdown);
+        (output, map)
+    }
+
+    /// Edits that turn the input into the output of [`remove`](Self::remove)
+    ///
+    /// Returns the smallest list of non-overlapping edits, sorted by position,
+    /// with ranges in the input measured in `encoding`. Apply them from last to
+    /// first (or shift later ranges) so editors keep cursors, undo history and
+    /// collaborative state instead of replacing the whole document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, OffsetEncoding, TextEdit};
+    ///
+    /// let remover = CitationRemover::new();
+    /// let edits = remover.text_edits("Café[1] au lait[2].", OffsetEncoding::Utf16);
+    /// assert_eq!(
+    ///     edits,
+    ///     vec![
+    ///         TextEdit { range: 4..7, replacement: String::new() },
+    ///         TextEdit { range: 15..18, replacement: String::new() },
+    ///     ]
+    /// );
+    /// ```
+    pub fn text_edits(&self, markdown: &str, encoding: OffsetEncoding) -> Vec<TextEdit> {
+        let (output, map) = self.remove_with_offset_map(markdown);
+        text_edit::text_edits(&map, markdown, &output, encoding)
+    }
+
+    /// Remove citations and keep everything removed in a sidecar
+    ///
+    /// The sidecar holds every removed marker, definition, header and
+    /// whitespace run with the text around it, so
+    /// [`restore_citations`](crate::restore_citations) can put it back, even
+    /// after the cleaned text has been edited. Enable the `serde` feature to
+    /// store it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{restore_citations, CitationRemover};
+    ///
+    /// let input = "Rust is fast[1].\n\n[1]: https://rust-lang.org";
+    /// let (output, sidecar) = CitationRemover::new().remove_reversible(input);
+    /// assert_eq!(output, "Rust is fast.");
+    ///
+    /// let edited = output.replace("Rust", "Rust really");
+    /// let restored = restore_citations(&edited, &sidecar);
+    /// assert_eq!(restored.text, "Rust really is fast[1].\n\n[1]: https://rust-lang.org");
+    /// assert!(restored.unplaced.is_empty());
+    /// ```
+    pub fn remove_reversible(&self, markdown: &str) -> (String, CitationSidecar) {
+        let (output, report, map) = self.remove_recorded(markdown);
+        let edits = text_edit::text_edits(&map, markdown, &output, OffsetEncoding::Utf8);
+        let sidecar = CitationSidecar::new(markdown, &output, &edits, &report);
+        (output, sidecar)
+    }
+
+    /// Remove citations from raw bytes, failing if they are not valid UTF-8
+    pub fn try_remove(&self, markdown: Vec<u8>) -> Result<String> {
+        Ok(self.remove(&String::from_utf8(markdown)?))
+    }
+
+    /// Like [`remove_with_report`](Self::remove_with_report) for raw bytes,
+    /// failing if they are not valid UTF-8
+    pub fn try_remove_with_report(&self, markdown: Vec<u8>) -> Result<(String, RemovalReport)> {
+        Ok(self.remove_with_report(&String::from_utf8(markdown)?))
+    }
+
+    /// Convert citations into another format instead of removing them
+    ///
+    /// Inline markers (`[1]`, `[^1_1]`, `[source:1]`, `【1†source】`) are rewritten
+    /// and numbered in order of first appearance, and the reference block is
+    /// rebuilt from the definitions they point to. Markers without a matching
+    /// definition are removed. Whitespace cleanup follows the configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationFormat, CitationRemover};
+    ///
+    /// let remover = CitationRemover::new();
+    /// let input = "Rust is fast[1].\n\n[1]: https://rust-lang.org \"Rust\"";
+    /// let output = remover.convert(input, CitationFormat::Footnotes);
+    /// assert_eq!(output, "Rust is fast[^1].\n\n[^1]: [Rust](https://rust-lang.org)");
+    /// ```
+    pub fn convert(&self, markdown: &str, format: CitationFormat) -> String {
+        let converted = convert::convert(markdown, &self.patterns, format);
+        let body = self.cleanup(markdown, converted.body);
+        let ending = if markdown.ends_with('\n') { "\n" } else { "" };
 
-        // Step 2: Remove inline citations
-        if self.config.remove_inline_citations {
-            result = self.remove_inline_citations(&result);
+        if converted.appendix.is_empty() {
+            body
+        } else if body.trim().is_empty() {
+            format!("{}{}", converted.appendix, ending)
+        } else {
+            format!("{}\n\n{}{}", body.trim_end(), converted.appendix, ending)
         }
+    }
 
-        // Step 3: Cleanup whitespace
-        if self.config.normalize_whitespace {
-            result = self.normalize_whitespace(&result);
+    /// Renumber the remaining citations from 1 in order of first appearance
+    ///
+    /// Inline markers keep their syntax and definitions keep their layout, but
+    /// both are renumbered without gaps and the definitions are put in order.
+    /// Definitions pointing to the same URL (ignoring scheme, `www.`, trailing
+    /// slashes and `utm_*` parameters) are merged. Markers without a definition
+    /// are numbered after every definition. Citations in code are left alone.
+    /// Whitespace cleanup follows the configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::CitationRemover;
+    ///
+    /// let remover = CitationRemover::new();
+    /// let input = "A[4] B[7].\n\n[4]: https://a.com\n[7]: https://a.com/";
+    /// assert_eq!(remover.renumber(input), "A[1] B[1].\n\n[1]: https://a.com");
+    /// ```
+    pub fn renumber(&self, markdown: &str) -> String {
+        self.cleanup(markdown, normalize::renumber(markdown, &self.patterns))
+    }
+
+    /// Normalize mixed citation syntaxes into one style without removing them
+    ///
+    /// Every recognised marker (`[1]`, `[^1_3]`, `【2†source】`, `[source:4]`,
+    /// `[cite: 5]`) is rewritten into the target style and renumbered as in
+    /// [`renumber`](Self::renumber). The reference block is rewritten into the
+    /// same style, and markers are moved across adjacent punctuation according
+    /// to the placement option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationRemover, CitationStyle, MarkerPlacement, NormalizeOptions};
+    ///
+    /// let remover = CitationRemover::new();
+    /// let options = NormalizeOptions {
+    ///     style: CitationStyle::Footnote,
+    ///     placement: MarkerPlacement::BeforePunctuation,
+    /// };
+    /// let input = "Fact.[source:4]\n\n[4]: https://example.com";
+    /// assert_eq!(
+    ///     remover.normalize(input, options),
+    ///     "Fact[^1].\n\n[^1]: <https://example.com>"
+    /// );
+    /// ```
+    pub fn normalize(&self, markdown: &str, options: NormalizeOptions) -> String {
+        self.cleanup(
+            markdown,
+            normalize::normalize(markdown, &self.patterns, options),
+        )
+    }
+
+    /// Parse the citations in a document without changing it
+    ///
+    /// Returns every inline citation (kind, identifier, byte range, line and
+    /// column) and every reference definition or bibliography entry (label, URL,
+    /// title, raw text, range), linked to each other. Unresolved citations and
+    /// unused sources are available through
+    /// [`CitationAnalysis::unresolved`] and [`CitationAnalysis::unused`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationKind, CitationRemover};
+    ///
+    /// let remover = CitationRemover::new();
+    /// let analysis = remover.analyze("Fact[1].\n\n[1]: https://example.com \"Example\"");
+    ///
+    /// let citation = &analysis.citations[0];
+    /// assert_eq!(citation.kind, CitationKind::Numeric);
+    /// assert_eq!((citation.line, citation.column), (1, 5));
+    ///
+    /// let source = analysis.source_of(citation).unwrap();
+    /// assert_eq!(source.url.as_deref(), Some("https://example.com"));
+    /// assert_eq!(source.title.as_deref(), Some("Example"));
+    /// ```
+    pub fn analyze(&self, markdown: &str) -> CitationAnalysis {
+        analysis::analyze(markdown, &self.patterns)
+    }
+
+    /// Run the pipeline, recording removals and preserved segments
+    fn remove_recorded(&self, markdown: &str) -> (String, RemovalReport, OffsetMap) {
+        let sources = self.sources(markdown);
+        let mut recorder = Recorder::new(markdown);
+        let output = self.run(
+            markdown.to_string(),
+            &self.stages(),
+            &sources,
+            markdown.ends_with('\n'),
+            Some(&mut recorder),
+        );
+        let map = OffsetMap::new(recorder.origins(), markdown.len(), output.len());
+        let report = recorder.finish(&output);
+        (output, report, map)
+    }
+
+    /// Apply the configured whitespace cleanup steps to `result`, derived from
+    /// `markdown`
+    fn cleanup(&self, markdown: &str, result: String) -> String {
+        let stages: Vec<Arc<dyn Stage>> = [
+            BuiltinStage::Whitespace,
+            BuiltinStage::BlankLines,
+            BuiltinStage::TrimLines,
+        ]
+        .into_iter()
+        .filter(|stage| stage.enabled(&self.config))
+        .map(|stage| Arc::new(stage) as Arc<dyn Stage>)
+        .collect();
+        let final_newline = markdown.ends_with('\n');
+        self.run(result, &stages, &HashMap::new(), final_newline, None)
+    }
+
+    /// Stages to run: the custom pipeline, or the built-in stages enabled by
+    /// the configuration
+    ///
+    /// References go first because inline citation removal would break
+    /// reference link patterns; whitespace cleanup goes last.
+    fn stages(&self) -> Cow<'_, [Arc<dyn Stage>]> {
+        match &self.pipeline {
+            Some(pipeline) => Cow::Borrowed(pipeline.stages()),
+            None => Cow::Owned(Pipeline::from_config(&self.config).stages().to_vec()),
         }
+    }
 
-        // Step 4: Remove excessive blank lines
-        if self.config.remove_blank_lines {
-            result = self.remove_excessive_blank_lines(&result);
+    /// Sources defined in the input by label, the first definition winning;
+    /// only looked up for custom pipelines or when the replacement needs them
+    fn sources(&self, input: &str) -> HashMap<String, Source> {
+        let mut sources = HashMap::new();
+        if self.pipeline.is_some() || self.config.replacement.needs_sources() {
+            for source in self.analyze(input).sources {
+                sources.entry(source.label.clone()).or_insert(source);
+            }
         }
+        sources
+    }
 
-        // Step 5: Trim lines
-        if self.config.trim_lines {
-            result = self.trim_all_lines(&result);
+    /// Run `stages` over `text`, recording each stage's edits
+    ///
+    /// `final_newline` tells whether the document ended with a line break,
+    /// which trimming keeps.
+    fn run(
+        &self,
+        mut text: String,
+        stages: &[Arc<dyn Stage>],
+        sources: &HashMap<String, Source>,
+        final_newline: bool,
+        mut recorder: Option<&mut Recorder>,
+    ) -> String {
+        for stage in stages {
+            let stage_edits = {
+                let context = StageContext::new(self, sources, &text, final_newline);
+                pipeline::sanitize(&text, stage.edits(&text, &context))
+            };
+            if stage_edits.is_empty() {
+                continue;
+            }
+            if let Some(recorder) = recorder.as_deref_mut() {
+                recorder.record(&text, &stage_edits);
+            }
+            let replacements = stage_edits
+                .into_iter()
+                .map(|e| (e.range, e.replacement))
+                .collect();
+            text = edits::splice(&text, replacements);
         }
+        text
+    }
 
-        result
+    /// Vendor-specific leftovers enabled in the configuration, outside code
+    pub(crate) fn artifact_deletions(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+        self.config
+            .artifacts
+            .iter()
+            .flat_map(|&artifact| {
+                artifact
+                    .ranges(text)
+                    .into_iter()
+                    .map(move |range| StageEdit {
+                        range,
+                        replacement: String::new(),
+                        rule: RuleId::Artifact(artifact),
+                        continues: false,
+                    })
+            })
+            .filter(|edit| !context.is_protected(edit.range.clone()))
+            .collect()
+    }
+
+    /// Inline citations, using comprehensive pattern matching
+    /// Handles: `[1]`, `[^1]`, `[^1_1]`, `[source:1]`, `[cite: 5]`, `【1†source】`
+    ///
+    /// Markers are replaced according to the configured
+    /// [`Replacement`](crate::Replacement). Markers are only parsed when the
+    /// strategy looks at them or custom patterns need classifying.
+    pub(crate) fn inline_edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+        let mut edits = self.marker_edits(text, context);
+        if self.config.replacement.is_delete() {
+            join_cjk_spacing(text, &mut edits);
+        }
+        edits
     }
 
-    /// Remove ALL inline citations using comprehensive pattern matching
-    /// Handles: `[1]`, `[^1]`, `[^1_1]`, `[source:1]`, `[@smith2004]`, `@citation`
-    fn remove_inline_citations(&self, text: &str) -> String {
-        // Use the unified comprehensive pattern that matches ALL citation formats
+    fn marker_edits(&self, text: &str, context: &StageContext) -> Vec<StageEdit> {
+        let replacement = &self.config.replacement;
+        if replacement.needs_marker() || self.patterns.has_custom_inline() {
+            return citations::markers(text, &self.patterns, &[], context.protected_ranges())
+                .into_iter()
+                .map(|marker| {
+                    let citation = CitationMatch {
+                        text: &text[marker.range.clone()],
+                        kind: marker.kind,
+                        id: &marker.id,
+                        prefix: marker.prefix.as_deref(),
+                        source: context.source_of(&marker),
+                    };
+                    StageEdit {
+                        range: marker.range.clone(),
+                        replacement: replacement.render(&citation),
+                        rule: RuleId::inline(marker.kind),
+                        continues: false,
+                    }
+                })
+                .collect();
+        }
+
+        let fixed = match replacement {
+            Replacement::Text(text) => text.as_str(),
+            _ => "",
+        };
         self.patterns
             .inline_citations
-            .replace_all(text, "")
-            .to_string()
+            .find_iter(text)
+            .map(|m| StageEdit {
+                range: m.range(),
+                replacement: fixed.to_string(),
+                rule: RuleId::inline(CitationKind::of_marker(m.as_str())),
+                continues: false,
+            })
+            .collect()
     }
 
-    /// Remove reference sections at end of document
+    /// Reference definitions, entries and sections
     /// Handles: `[1]: url`, `[^1]: text`, `[^1_1]: url`, `[1](url)`, `[^1_1](url)`
-    fn remove_reference_sections(&self, text: &str) -> String {
-        let lines: Vec<&str> = text.lines().collect();
-        let mut references_start = None;
-
-        // Scan for reference section start (find the FIRST occurrence)
-        for (i, line) in lines.iter().enumerate() {
-            // Skip if we already found the start
-            if references_start.is_some() {
-                break;
-            }
+    ///
+    /// Each definition is removed as a whole block, including continuation lines,
+    /// wherever it appears in the document. Reference headers take their section
+    /// body with them. Blank lines between removed blocks go with the block before
+    /// them; the rest of a section around the definitions it contains is reported
+    /// as the section.
+    pub(crate) fn reference_deletions(&self, text: &str) -> Vec<StageEdit> {
+        let blocks: Vec<Block> = blocks::scan(text, &self.patterns)
+            .into_iter()
+            .filter(|block| self.removes_block(block.kind))
+            .collect();
+        let (sections, definitions): (Vec<&Block>, Vec<&Block>) = blocks
+            .iter()
+            .partition(|block| block.kind == BlockKind::Section);
+        let merged = blocks::merge_ranges(text, blocks.iter().map(|block| block.range.clone()));
 
-            // Check for reference header
-            if self.config.remove_reference_headers && self.patterns.reference_header.is_match(line)
-            {
-                references_start = Some(i);
-                break;
+        let mut deletions: Vec<StageEdit> = Vec::new();
+        let mut reported = vec![false; sections.len()];
+        let mut fill = |deletions: &mut Vec<StageEdit>, gap: Range<usize>| {
+            if text[gap.clone()].trim().is_empty() {
+                if let Some(last) = deletions.last_mut().filter(|d| d.range.end == gap.start) {
+                    last.range.end = gap.end;
+                    return;
+                }
+            }
+            let mut pos = gap.start;
+            while pos < gap.end {
+                let section = sections
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| s.range.contains(&pos))
+                    .max_by_key(|(_, s)| s.range.start);
+                match section {
+                    Some((i, s)) => {
+                        let end = s.range.end.min(gap.end);
+                        deletions.push(StageEdit {
+                            range: pos..end,
+                            replacement: String::new(),
+                            rule: RuleId::ReferenceSection,
+                            continues: reported[i],
+                        });
+                        reported[i] = true;
+                        pos = end;
+                    }
+                    None => {
+                        let end = sections
+                            .iter()
+                            .map(|s| s.range.start)
+                            .filter(|&start| start > pos && start < gap.end)
+                            .min()
+                            .unwrap_or(gap.end);
+                        if let Some(last) = deletions.last_mut() {
+                            last.range.end = end;
+                        }
+                        pos = end;
+                    }
+                }
             }
+        };
 
-            // Check for ANY reference definition format using comprehensive pattern
-            if self.patterns.reference_definitions.is_match(line)
-                || self.patterns.reference_entry.is_match(line)
+        for range in merged {
+            let mut pos = range.start;
+            for definition in definitions
+                .iter()
+                .filter(|d| range.start <= d.range.start && d.range.end <= range.end)
             {
-                references_start = Some(i);
-                break;
+                fill(&mut deletions, pos..definition.range.start);
+                deletions.push(StageEdit {
+                    range: definition.range.clone(),
+                    replacement: String::new(),
+                    rule: match definition.kind {
+                        BlockKind::Footnote => RuleId::FootnoteDefinition,
+                        BlockKind::LinkLine => RuleId::LinkLine,
+                        BlockKind::Entry => RuleId::ReferenceEntry,
+                        BlockKind::Custom => RuleId::CustomDefinition,
+                        _ => RuleId::LinkDefinition,
+                    },
+                    continues: false,
+                });
+                pos = definition.range.end;
             }
+            fill(&mut deletions, pos..range.end);
         }
+        deletions
+    }
 
-        // Remove everything from references onward
-        if let Some(start) = references_start {
-            lines[..start].join("\n")
-        } else {
-            text.to_string()
+    /// Whether the configuration removes blocks of the given kind
+    fn removes_block(&self, kind: BlockKind) -> bool {
+        match kind {
+            BlockKind::Footnote
+            | BlockKind::LinkDefinition
+            | BlockKind::LinkLine
+            | BlockKind::Custom => self.config.remove_reference_links,
+            BlockKind::Entry => self.config.remove_reference_entries,
+            BlockKind::Section => self.config.remove_reference_headers,
         }
     }
 
     /// Normalize multiple spaces to single space
-    fn normalize_whitespace(&self, text: &str) -> String {
+    pub(crate) fn whitespace_deletions(&self, text: &str) -> Vec<StageEdit> {
         self.patterns
             .multiple_whitespace
-            .replace_all(text, " ")
-            .to_string()
+            .find_iter(text)
+            .map(|m| StageEdit {
+                range: m.start() + 1..m.end(),
+                replacement: String::new(),
+                rule: RuleId::MultipleSpaces,
+                continues: false,
+            })
+            .collect()
     }
 
     /// Remove excessive blank lines (3+ consecutive newlines → 2)
-    fn remove_excessive_blank_lines(&self, text: &str) -> String {
+    pub(crate) fn blank_line_deletions(&self, text: &str) -> Vec<StageEdit> {
         self.patterns
             .excessive_newlines
-            .replace_all(text, "\n\n")
-            .to_string()
+            .find_iter(text)
+            .map(|m| StageEdit {
+                range: m.start() + 2..m.end(),
+                replacement: String::new(),
+                rule: RuleId::ExcessiveBlankLines,
+                continues: false,
+            })
+            .collect()
     }
 
-    /// Trim whitespace from all lines
-    fn trim_all_lines(&self, text: &str) -> String {
-        text.lines()
-            .map(|line| line.trim_end())
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Trim whitespace from the end of every line and blank lines from the end
+    /// of the text, which ends with a single line break if `final_newline`
+    pub(crate) fn trailing_whitespace_deletions(
+        &self,
+        text: &str,
+        final_newline: bool,
+    ) -> Vec<StageEdit> {
+        let end = text.trim_end().len();
+        let mut edits = Vec::new();
+        let mut start = 0;
+        for line in text[..end].split('\n') {
+            let trimmed = line.trim_end().len();
+            if trimmed < line.len() {
+                edits.push((start + trimmed..start + line.len(), ""));
+            }
+            start += line.len() + 1;
+        }
+        let ending = if final_newline && end > 0 { "\n" } else { "" };
+        if text[end..].starts_with(ending) {
+            if end + ending.len() < text.len() {
+                edits.push((end + ending.len()..text.len(), ""));
+            }
+        } else {
+            edits.push((end..text.len(), ending));
+        }
+        edits
+            .into_iter()
+            .map(|(range, replacement)| StageEdit {
+                range,
+                replacement: replacement.to_string(),
+                rule: RuleId::TrailingWhitespace,
+                continues: false,
+            })
+            .collect()
     }
 }
 
@@ -138,15 +677,80 @@ impl Default for CitationRemover {
     }
 }
 
+/// Take the spaces around deleted markers in CJK text with them
+///
+/// Chinese and Japanese put no spaces between words, so `首都 [1] 人口` must
+/// become `首都人口` rather than `首都 人口`. Markers separated only by spaces
+/// are treated as one run.
+fn join_cjk_spacing(text: &str, edits: &mut [StageEdit]) {
+    let mut first = 0;
+    while first < edits.len() {
+        let mut last = first;
+        while last + 1 < edits.len()
+            && text[edits[last].range.end..edits[last + 1].range.start]
+                .bytes()
+                .all(|b| b == b' ')
+        {
+            last += 1;
+        }
+
+        let before = text[..edits[first].range.start].trim_end_matches(' ');
+        let after = text[edits[last].range.end..].trim_start_matches(' ');
+        let between_cjk = before.chars().next_back().is_some_and(is_cjk)
+            && after.chars().next().is_some_and(is_cjk);
+        if between_cjk {
+            edits[first].range.start = before.len();
+            for i in first..last {
+                edits[i].range.end = edits[i + 1].range.start;
+            }
+            edits[last].range.end = text.len() - after.len();
+        }
+        first = last + 1;
+    }
+}
+
+/// Chinese and Japanese characters and punctuation, which are written
+/// without spaces between words (Korean Hangul is not)
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}'
+        | '\u{20000}'..='\u{3FFFF}'
+    ) && !matches!(c, '\u{3130}'..='\u{318F}')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Run a single built-in stage
+    fn apply(remover: &CitationRemover, stage: BuiltinStage, text: &str) -> String {
+        let final_newline = text.ends_with('\n');
+        remover.run(
+            text.to_string(),
+            &[Arc::new(stage)],
+            &HashMap::new(),
+            final_newline,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_cjk_spacing_around_markers() {
+        let remover = CitationRemover::new();
+        assert_eq!(remover.remove("北京是首都 [1] 。"), "北京是首都。");
+        assert_eq!(remover.remove("首都 [1] [2] 人口"), "首都人口");
+        assert_eq!(remover.remove("收入增长了40%［3］。"), "收入增长了40%。");
+        assert_eq!(remover.remove("서울은 [1] 수도"), "서울은 수도");
+        assert_eq!(remover.remove("Paris [1] est"), "Paris est");
+    }
+
     #[test]
     fn test_remove_inline_numeric() {
         let remover = CitationRemover::new();
         let input = "Text[1] with[2] citations[3].";
-        let result = remover.remove_inline_citations(input);
+        let result = apply(&remover, BuiltinStage::InlineCitations, input);
         assert_eq!(result, "Text with citations.");
     }
 
@@ -154,7 +758,7 @@ mod tests {
     fn test_remove_inline_named() {
         let remover = CitationRemover::new();
         let input = "Text[source:1] with[ref:2] citations.";
-        let result = remover.remove_inline_citations(input);
+        let result = apply(&remover, BuiltinStage::InlineCitations, input);
         assert_eq!(result, "Text with citations.");
     }
 
@@ -162,7 +766,7 @@ mod tests {
     fn test_normalize_whitespace() {
         let remover = CitationRemover::new();
         let input = "Text  with    multiple     spaces.";
-        let result = remover.normalize_whitespace(input);
+        let result = apply(&remover, BuiltinStage::Whitespace, input);
         assert_eq!(result, "Text with multiple spaces.");
     }
 
@@ -170,7 +774,7 @@ mod tests {
     fn test_remove_excessive_blank_lines() {
         let remover = CitationRemover::new();
         let input = "Line 1\n\n\n\n\nLine 2";
-        let result = remover.remove_excessive_blank_lines(input);
+        let result = apply(&remover, BuiltinStage::BlankLines, input);
         assert_eq!(result, "Line 1\n\nLine 2");
     }
 
@@ -178,15 +782,32 @@ mod tests {
     fn test_trim_all_lines() {
         let remover = CitationRemover::new();
         let input = "Line 1   \nLine 2  \nLine 3 ";
-        let result = remover.trim_all_lines(input);
+        let result = apply(&remover, BuiltinStage::TrimLines, input);
         assert_eq!(result, "Line 1\nLine 2\nLine 3");
     }
 
+    #[test]
+    fn test_final_newline_follows_input() {
+        let remover = CitationRemover::new();
+        assert_eq!(
+            remover.remove("Rust is fast[1].\n\n[1]: https://rust-lang.org"),
+            "Rust is fast."
+        );
+        assert_eq!(
+            remover.remove("Rust is fast[1].\n\n[1]: https://rust-lang.org\n"),
+            "Rust is fast.\n"
+        );
+        assert_eq!(remover.remove("A\n\n## References\n- x"), "A");
+        assert_eq!(remover.remove("Clean text.\n"), "Clean text.\n");
+        assert_eq!(remover.remove("Clean text.  \n\n\n"), "Clean text.\n");
+        assert_eq!(remover.remove("[1]: https://rust-lang.org\n"), "");
+    }
+
     #[test]
     fn test_remove_reference_sections_with_header() {
         let remover = CitationRemover::new();
         let input = "Content here.\n\n## References\n[1]: https://example.com";
-        let result = remover.remove_reference_sections(input);
+        let result = apply(&remover, BuiltinStage::References, input);
         assert_eq!(result.trim(), "Content here.");
     }
 
@@ -194,7 +815,7 @@ mod tests {
     fn test_remove_reference_sections_without_header() {
         let remover = CitationRemover::new();
         let input = "Content here.\n\n[1]: https://example.com\n[2]: https://test.com";
-        let result = remover.remove_reference_sections(input);
+        let result = apply(&remover, BuiltinStage::References, input);
         assert_eq!(result.trim(), "Content here.");
     }
 
@@ -208,6 +829,7 @@ mod tests {
             normalize_whitespace: false,
             remove_blank_lines: false,
             trim_lines: false,
+            ..Default::default()
         };
         let remover = CitationRemover::with_config(config);
         let input = "Text[1].\n\n[1]: https://example.com";
@@ -226,4 +848,62 @@ mod tests {
         assert!(!result.contains("  "));
         assert!(!result.contains("\n\n\n"));
     }
+
+    #[test]
+    fn test_report_splits_section_around_definitions() {
+        let remover = CitationRemover::new();
+        let input =
+            "Text[1].\n\n## References\n\n[1]: https://a.com\n\n[2]: https://b.com\n- Extra\n";
+        let (output, report) = remover.remove_with_report(input);
+        assert_eq!(output, "Text.\n");
+
+        let pieces: Vec<_> = report
+            .removals
+            .iter()
+            .map(|r| (r.rule.clone(), r.text.as_str()))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (RuleId::ReferenceSection, "## References\n\n"),
+                (RuleId::LinkDefinition, "[1]: https://a.com\n\n"),
+                (RuleId::LinkDefinition, "[2]: https://b.com\n"),
+                (RuleId::ReferenceSection, "- Extra\n"),
+                (RuleId::InlineNumeric, "[1]"),
+                (RuleId::TrailingWhitespace, "\n"),
+            ]
+        );
+        assert_eq!(report.counts.headers, 1);
+        assert_eq!(report.counts.definitions, 2);
+        assert_eq!(report.counts.whitespace, 1);
+    }
+
+    #[test]
+    fn test_report_ranges_point_into_input() {
+        let remover = CitationRemover::new();
+        let input = "A[1]  b[^2] c.  \n\n\n\nD【3†source】\n\n[1]: https://a.com\n[^2]: Note";
+        let (_, report) = remover.remove_with_report(input);
+
+        for removal in &report.removals {
+            if removal.rule.category() != crate::RemovalCategory::Whitespace {
+                assert_eq!(&input[removal.range.clone()], removal.text);
+            }
+        }
+        let spaces = report.by_rule(RuleId::MultipleSpaces).next().unwrap();
+        assert_eq!(
+            (spaces.range.clone(), spaces.line, spaces.column),
+            (5..6, 1, 6)
+        );
+        let blank = report.by_rule(RuleId::ExcessiveBlankLines).next().unwrap();
+        assert_eq!((blank.line, blank.text.as_str()), (3, "\n\n"));
+        assert_eq!(report.counts.inline, 3);
+        assert_eq!(report.output_bytes, remover.remove(input).len());
+    }
+
+    #[test]
+    fn test_try_remove_rejects_invalid_utf8() {
+        let remover = CitationRemover::new();
+        assert!(remover.try_remove(vec![b'a', 0xff]).is_err());
+        assert_eq!(remover.try_remove(b"Text[1].".to_vec()).unwrap(), "Text.");
+    }
 }
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Source;
+use crate::citations::CitationKind;
+
+/// An inline citation about to be replaced
+#[derive(Debug, Clone, Copy)]
+pub struct CitationMatch<'a> {
+    /// The marker as written (`[1]`, `[^1_2]`, `【3†source】`)
+    pub text: &'a str,
+    pub kind: CitationKind,
+    /// Identifier inside the marker (`1`, `1_2`, `note`)
+    pub id: &'a str,
+    /// Prefix of a named citation (`source` in `[source:1]`)
+    pub prefix: Option<&'a str>,
+    /// The definition the marker refers to in the input, if any
+    pub source: Option<&'a Source>,
+}
+
+/// Produces the text an inline citation is replaced with
+///
+/// Implemented for every `Fn(&CitationMatch) -> String`, so a closure can be
+/// passed to [`Replacement::custom`].
+pub trait Replacer: Send + Sync {
+    fn replace(&self, citation: &CitationMatch) -> String;
+}
+
+impl<F> Replacer for F
+where
+    F: Fn(&CitationMatch) -> String + Send + Sync,
+{
+    fn replace(&self, citation: &CitationMatch) -> String {
+        self(citation)
+    }
+}
+
+/// What inline citation markers are replaced with
+///
+/// Reference definitions and sections are always deleted; the strategy only
+/// applies to inline markers.
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Replacement {
+    /// Delete the marker
+    #[default]
+    Delete,
+    /// Replace every marker with the same text, e.g. `†`
+    Text(String),
+    /// Hide the marker in an HTML comment: `<!-- cite:3 -->`, `<!-- cite:source:3 -->`
+    HtmlComment,
+    /// Let a [`Replacer`] decide
+    ///
+    /// Closures cannot be serialized; with the `serde` feature, serializing
+    /// this variant fails and configuration files cannot select it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn Replacer>),
+}
+
+impl Replacement {
+    /// Replace markers with the output of a closure or [`Replacer`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_ai_cite_remove::{CitationMatch, CitationRemover, RemoverConfig, Replacement};
+    ///
+    /// let config = RemoverConfig {
+    ///     replacement: Replacement::custom(|citation: &CitationMatch| {
+    ///         match citation.source.and_then(|s| s.url.as_deref()) {
+    ///             Some(url) => format!(" ({})", url),
+    ///             None => String::new(),
+    ///         }
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    /// let remover = CitationRemover::with_config(config);
+    /// let output = remover.remove("Rust is fast[1].\n\n[1]: https://rust-lang.org");
+    /// assert_eq!(output, "Rust is fast (https://rust-lang.org).");
+    /// ```
+    pub fn custom(replacer: impl Replacer + 'static) -> Self {
+        Self::Custom(Arc::new(replacer))
+    }
+
+    /// Whether markers are deleted outright
+    pub fn is_delete(&self) -> bool {
+        matches!(self, Self::Delete)
+    }
+
+    /// Whether rendering needs the marker's identifier or source
+    pub(crate) fn needs_marker(&self) -> bool {
+        matches!(self, Self::HtmlComment | Self::Custom(_))
+    }
+
+    /// Whether rendering needs the marker's resolved source
+    pub(crate) fn needs_sources(&self) -> bool {
+        matches!(self, Self::Custom(_))
+    }
+
+    /// Text to put in place of `citation`
+    pub(crate) fn render(&self, citation: &CitationMatch) -> String {
+        match self {
+            Self::Delete => String::new(),
+            Self::Text(text) => text.clone(),
+            Self::HtmlComment => match citation.prefix {
+                Some(prefix) => format!("<!-- cite:{}:{} -->", prefix, citation.id),
+                None => format!("<!-- cite:{} -->", citation.id),
+            },
+            Self::Custom(replacer) => replacer.replace(citation),
+        }
+    }
+}
+
+impl fmt::Debug for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delete => f.write_str("Delete"),
+            Self::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Self::HtmlComment => f.write_str("HtmlComment"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn citation<'a>(id: &'a str, prefix: Option<&'a str>) -> CitationMatch<'a> {
+        CitationMatch {
+            text: "[1]",
+            kind: CitationKind::Numeric,
+            id,
+            prefix,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_render_strategies() {
+        let plain = citation("3", None);
+        assert_eq!(Replacement::Delete.render(&plain), "");
+        assert_eq!(Replacement::Text("†".into()).render(&plain), "†");
+        assert_eq!(Replacement::HtmlComment.render(&plain), "<!-- cite:3 -->");
+        assert_eq!(
+            Replacement::HtmlComment.render(&citation("3", Some("source"))),
+            "<!-- cite:source:3 -->"
+        );
+
+        let custom = Replacement::custom(|c: &CitationMatch| format!("<{}>", c.id));
+        assert_eq!(custom.render(&plain), "<3>");
+        assert_eq!(format!("{:?}", custom), "Custom(..)");
+    }
+}
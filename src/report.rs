@@ -0,0 +1,339 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::artifacts::Artifact;
+use crate::citations::CitationKind;
+use crate::edits::Origins;
+use crate::pipeline::StageEdit;
+use crate::position::LineIndex;
+
+/// Rule that removed a piece of text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RuleId {
+    /// Inline `[1]`
+    InlineNumeric,
+    /// Inline `[^1]`, `[^1_1]`
+    InlineFootnote,
+    /// Inline `[source:1]`, `[cite: 5]`
+    InlineNamed,
+    /// Inline `【1†source】`
+    InlineLenticular,
+    /// Inline marker matched by a user-supplied pattern
+    InlineCustom,
+    /// `[1]: https://...` link reference definition
+    LinkDefinition,
+    /// `[^1]: ...` footnote definition
+    FootnoteDefinition,
+    /// `[1](https://...)` link line
+    LinkLine,
+    /// Definition matched by a user-supplied pattern
+    CustomDefinition,
+    /// `[1] Author, A. (2024). Title.` bibliographic entry
+    ReferenceEntry,
+    /// `## References` header and the rest of its section
+    ReferenceSection,
+    /// Runs of spaces collapsed to one
+    MultipleSpaces,
+    /// Blank lines beyond the first
+    ExcessiveBlankLines,
+    /// Whitespace at the end of a line or of the document
+    TrailingWhitespace,
+    /// Vendor-specific leftover such as `:contentReference[oaicite:0]`
+    Artifact(Artifact),
+    /// Edit made by a custom pipeline [`Stage`](crate::Stage), named by it
+    Custom(Cow<'static, str>),
+}
+
+impl RuleId {
+    /// Rule of a custom pipeline stage named `name`
+    pub fn custom(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::Custom(name.into())
+    }
+
+    /// Stable kebab-case identifier, e.g. `inline-numeric`
+    ///
+    /// Artifact rules return the artifact's identifier, e.g. `cite-turn`;
+    /// custom rules the name they were given.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::InlineNumeric => "inline-numeric",
+            Self::InlineFootnote => "inline-footnote",
+            Self::InlineNamed => "inline-named",
+            Self::InlineLenticular => "inline-lenticular",
+            Self::InlineCustom => "inline-custom",
+            Self::LinkDefinition => "link-definition",
+            Self::FootnoteDefinition => "footnote-definition",
+            Self::LinkLine => "link-line",
+            Self::CustomDefinition => "custom-definition",
+            Self::ReferenceEntry => "reference-entry",
+            Self::ReferenceSection => "reference-section",
+            Self::MultipleSpaces => "multiple-spaces",
+            Self::ExcessiveBlankLines => "excessive-blank-lines",
+            Self::TrailingWhitespace => "trailing-whitespace",
+            Self::Artifact(artifact) => artifact.as_str(),
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// Category the rule is counted under in [`RemovalCounts`]
+    pub fn category(&self) -> RemovalCategory {
+        match self {
+            Self::InlineNumeric
+            | Self::InlineFootnote
+            | Self::InlineNamed
+            | Self::InlineLenticular
+            | Self::InlineCustom => RemovalCategory::Inline,
+            Self::LinkDefinition
+            | Self::FootnoteDefinition
+            | Self::LinkLine
+            | Self::CustomDefinition => RemovalCategory::Definition,
+            Self::ReferenceEntry => RemovalCategory::Entry,
+            Self::ReferenceSection => RemovalCategory::Header,
+            Self::MultipleSpaces | Self::ExcessiveBlankLines | Self::TrailingWhitespace => {
+                RemovalCategory::Whitespace
+            }
+            Self::Artifact(_) => RemovalCategory::Artifact,
+            Self::Custom(_) => RemovalCategory::Custom,
+        }
+    }
+
+    /// Every rule that is not parameterized
+    #[cfg(feature = "serde")]
+    const FIXED: &'static [RuleId] = &[
+        Self::InlineNumeric,
+        Self::InlineFootnote,
+        Self::InlineNamed,
+        Self::InlineLenticular,
+        Self::InlineCustom,
+        Self::LinkDefinition,
+        Self::FootnoteDefinition,
+        Self::LinkLine,
+        Self::CustomDefinition,
+        Self::ReferenceEntry,
+        Self::ReferenceSection,
+        Self::MultipleSpaces,
+        Self::ExcessiveBlankLines,
+        Self::TrailingWhitespace,
+    ];
+
+    /// Rule with the identifier `name`; unknown names are custom rules
+    #[cfg(feature = "serde")]
+    fn from_name(name: String) -> Self {
+        Self::FIXED
+            .iter()
+            .cloned()
+            .chain(
+                Artifact::ALL
+                    .iter()
+                    .map(|&artifact| Self::Artifact(artifact)),
+            )
+            .find(|rule| rule.as_str() == name)
+            .unwrap_or(Self::Custom(Cow::Owned(name)))
+    }
+
+    pub(crate) fn inline(kind: CitationKind) -> Self {
+        match kind {
+            CitationKind::Numeric => Self::InlineNumeric,
+            CitationKind::Footnote => Self::InlineFootnote,
+            CitationKind::Named => Self::InlineNamed,
+            CitationKind::Lenticular => Self::InlineLenticular,
+            CitationKind::Custom => Self::InlineCustom,
+        }
+    }
+}
+
+/// Serialized as its [`as_str`](RuleId::as_str) identifier
+#[cfg(feature = "serde")]
+impl Serialize for RuleId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for RuleId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Self::from_name(name))
+    }
+}
+
+impl fmt::Display for RuleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Group of rules totalled in [`RemovalCounts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum RemovalCategory {
+    Inline,
+    Definition,
+    Header,
+    Entry,
+    Whitespace,
+    /// Vendor-specific leftovers
+    Artifact,
+    /// Edits made by custom pipeline stages
+    Custom,
+}
+
+/// A piece of the input removed by one rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Removal {
+    pub rule: RuleId,
+    /// Byte range in the input
+    ///
+    /// Whitespace fixes run after earlier removals, so their range may enclose
+    /// text that an earlier removal already took out.
+    pub range: Range<usize>,
+    /// 1-based line number in the input
+    pub line: usize,
+    /// 1-based column in the input, counted in characters
+    pub column: usize,
+    /// The text that was removed
+    pub text: String,
+    /// Text put in its place, empty for deletions
+    pub replacement: String,
+}
+
+/// Number of removals per category
+///
+/// Blocks and matches are counted once, even when a reference section is
+/// reported as several removals around the definitions it contains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RemovalCounts {
+    /// Inline citation markers
+    pub inline: usize,
+    /// Link reference definitions, footnote definitions and link lines
+    pub definitions: usize,
+    /// Reference section headers
+    pub headers: usize,
+    /// Bibliographic entries
+    pub entries: usize,
+    /// Collapsed spaces, blank lines and trailing whitespace
+    pub whitespace: usize,
+    /// Vendor-specific leftovers such as `citeturn0search0`
+    pub artifacts: usize,
+    /// Edits made by custom pipeline stages
+    pub custom: usize,
+}
+
+impl RemovalCounts {
+    /// Citation removals, including artifacts but excluding whitespace fixes
+    pub fn citations(&self) -> usize {
+        self.inline + self.definitions + self.headers + self.entries + self.artifacts
+    }
+
+    /// All removals, including whitespace fixes and custom stages
+    pub fn total(&self) -> usize {
+        self.citations() + self.whitespace + self.custom
+    }
+
+    fn add(&mut self, category: RemovalCategory) {
+        match category {
+            RemovalCategory::Inline => self.inline += 1,
+            RemovalCategory::Definition => self.definitions += 1,
+            RemovalCategory::Header => self.headers += 1,
+            RemovalCategory::Entry => self.entries += 1,
+            RemovalCategory::Whitespace => self.whitespace += 1,
+            RemovalCategory::Artifact => self.artifacts += 1,
+            RemovalCategory::Custom => self.custom += 1,
+        }
+    }
+}
+
+/// What [`CitationRemover::remove_with_report`](crate::CitationRemover::remove_with_report) removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RemovalReport {
+    /// Removals in pipeline order, then input order
+    pub removals: Vec<Removal>,
+    pub counts: RemovalCounts,
+    /// Size of the input in bytes
+    pub input_bytes: usize,
+    /// Size of the output in bytes
+    pub output_bytes: usize,
+}
+
+impl RemovalReport {
+    /// Removals made by one rule
+    pub fn by_rule(&self, rule: RuleId) -> impl Iterator<Item = &Removal> {
+        self.removals.iter().filter(move |r| r.rule == rule)
+    }
+
+    /// Removals in one category
+    pub fn by_category(&self, category: RemovalCategory) -> impl Iterator<Item = &Removal> {
+        self.removals
+            .iter()
+            .filter(move |r| r.rule.category() == category)
+    }
+}
+
+/// Builds a [`RemovalReport`] while the pipeline runs
+pub(crate) struct Recorder<'a> {
+    input: &'a str,
+    index: LineIndex,
+    origins: Origins,
+    report: RemovalReport,
+}
+
+impl<'a> Recorder<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            index: LineIndex::new(input),
+            origins: Origins::new(input.len()),
+            report: RemovalReport {
+                input_bytes: input.len(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Record edits about to be applied to `text`, the current pipeline text
+    pub fn record(&mut self, text: &str, edits: &[StageEdit]) {
+        for edit in edits {
+            let range = self.origins.input_range(edit.range.clone());
+            let (line, column) = self.index.position(self.input, range.start);
+            if !edit.continues {
+                self.report.counts.add(edit.rule.category());
+            }
+            self.report.removals.push(Removal {
+                rule: edit.rule.clone(),
+                range,
+                line,
+                column,
+                text: text[edit.range.clone()].to_string(),
+                replacement: edit.replacement.clone(),
+            });
+        }
+        let ranges: Vec<_> = edits
+            .iter()
+            .map(|e| (e.range.clone(), e.replacement.len()))
+            .collect();
+        self.origins.replace(&ranges);
+    }
+
+    /// Which input bytes survived the edits recorded so far
+    pub fn origins(&self) -> &Origins {
+        &self.origins
+    }
+
+    pub fn finish(mut self, output: &str) -> RemovalReport {
+        self.report.output_bytes = output.len();
+        self.report
+    }
+}
@@ -0,0 +1,392 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::edits::splice;
+use crate::report::{RemovalCategory, RemovalReport};
+use crate::text_edit::TextEdit;
+
+/// Words of cleaned text kept on each side of a removal
+const CONTEXT_WORDS: usize = 6;
+/// Fewest words a shortened context may be cut down to
+const MIN_CONTEXT_WORDS: usize = 2;
+/// Fewest words with letters or digits on the side that anchors a piece when
+/// only the first word of the other side is still in place
+const ONE_SIDED_WORDS: usize = 3;
+
+/// What a [`SidecarEntry`] holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SidecarEntryKind {
+    /// Inline citation markers, with any whitespace removed next to them
+    Marker,
+    /// Reference definitions, entries or sections
+    Reference,
+    /// Whitespace removed by cleanup only
+    Whitespace,
+}
+
+/// One piece of removed text and where it was removed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SidecarEntry {
+    pub kind: SidecarEntryKind,
+    /// Removed text, inserted back verbatim
+    pub text: String,
+    /// Text put in its place in the cleaned text, empty for deletions
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub replacement: String,
+    /// Byte offset in the cleaned text where the text was removed
+    pub offset: usize,
+    /// Cleaned text just before the removal, up to a few words
+    pub before: String,
+    /// Cleaned text just after the removal, up to a few words
+    pub after: String,
+}
+
+/// Everything [`CitationRemover::remove_reversible`](crate::CitationRemover::remove_reversible) removed
+///
+/// With the `serde` feature enabled the sidecar can be stored next to the
+/// cleaned document, e.g. as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CitationSidecar {
+    /// Removed pieces in document order
+    pub entries: Vec<SidecarEntry>,
+    /// Length of the cleaned text in bytes
+    pub output_len: usize,
+}
+
+/// Result of [`restore_citations`](crate::restore_citations)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Restored {
+    /// Text with the removed pieces put back
+    pub text: String,
+    /// Markers whose surrounding text could no longer be found
+    pub unplaced: Vec<SidecarEntry>,
+}
+
+impl CitationSidecar {
+    /// Build a sidecar from the minimal edits that produced `output`
+    pub(crate) fn new(
+        input: &str,
+        output: &str,
+        edits: &[TextEdit],
+        report: &RemovalReport,
+    ) -> Self {
+        let mut removed = 0;
+        let mut inserted = 0;
+        let entries = edits
+            .iter()
+            .map(|edit| {
+                let offset = edit.range.start + inserted - removed;
+                let end = offset + edit.replacement.len();
+                removed += edit.range.len();
+                inserted += edit.replacement.len();
+                SidecarEntry {
+                    kind: entry_kind(report, edit),
+                    text: input[edit.range.clone()].to_string(),
+                    replacement: edit.replacement.clone(),
+                    offset,
+                    before: output[context_start(output, offset, CONTEXT_WORDS)..offset]
+                        .to_string(),
+                    after: output[end..context_end(output, end, CONTEXT_WORDS)].to_string(),
+                }
+            })
+            .collect();
+
+        Self {
+            entries,
+            output_len: output.len(),
+        }
+    }
+
+    /// Put the removed pieces back into `text`
+    ///
+    /// Each piece is re-anchored by the words around it and takes the place of
+    /// its replacement text, if it had one, so small edits to the cleaned text
+    /// are tolerated: the full context on both sides is tried first, then
+    /// shorter contexts, then a few words on one side with just the adjacent
+    /// word on the other. A piece is only placed where its context matches
+    /// exactly once.
+    ///
+    /// Markers that cannot be placed, or whose context matches in several
+    /// places, are left out and listed in [`Restored::unplaced`]. References
+    /// that cannot be placed are appended to the end of the document, and
+    /// whitespace is dropped.
+    pub fn restore(&self, text: &str) -> Restored {
+        let mut inserts = Vec::new();
+        let mut trailing = Vec::new();
+        let mut unplaced = Vec::new();
+
+        for entry in &self.entries {
+            match locate(text, entry) {
+                Some(position) => inserts.push((
+                    position..position + entry.replacement.len(),
+                    entry.text.clone(),
+                )),
+                None => match entry.kind {
+                    SidecarEntryKind::Marker => unplaced.push(entry.clone()),
+                    SidecarEntryKind::Reference => trailing.push(entry.text.as_str()),
+                    SidecarEntryKind::Whitespace => {}
+                },
+            }
+        }
+
+        let mut result = splice(text, inserts);
+        for reference in trailing {
+            if !result.is_empty() && !result.ends_with('\n') && !reference.starts_with('\n') {
+                result.push_str("\n\n");
+            }
+            result.push_str(reference);
+        }
+
+        Restored {
+            text: result,
+            unplaced,
+        }
+    }
+}
+
+/// Classify an edit by the most significant removal inside it
+fn entry_kind(report: &RemovalReport, edit: &TextEdit) -> SidecarEntryKind {
+    let categories = report
+        .removals
+        .iter()
+        .filter(|r| r.range.start < edit.range.end && edit.range.start < r.range.end)
+        .map(|r| r.rule.category());
+
+    let mut kind = SidecarEntryKind::Whitespace;
+    for category in categories {
+        match category {
+            RemovalCategory::Inline | RemovalCategory::Artifact | RemovalCategory::Custom => {
+                kind = SidecarEntryKind::Marker
+            }
+            RemovalCategory::Whitespace => {}
+            _ => return SidecarEntryKind::Reference,
+        }
+    }
+    kind
+}
+
+/// Where `entry` belongs in `text`, if its context identifies one place
+///
+/// Returns the start of the entry's replacement text, which must still be
+/// there.
+fn locate(text: &str, entry: &SidecarEntry) -> Option<usize> {
+    let replacement = entry.replacement.as_str();
+    if entry.before.is_empty() && entry.after.is_empty() {
+        return if entry.offset == 0 {
+            text.starts_with(replacement).then_some(0)
+        } else {
+            text.ends_with(replacement)
+                .then(|| text.len() - replacement.len())
+        };
+    }
+
+    // The text around the entry is unchanged
+    let needle = format!("{}{}{}", entry.before, replacement, entry.after);
+    let start = entry.offset - entry.before.len();
+    if text
+        .get(start..)
+        .is_some_and(|rest| rest.starts_with(&needle))
+    {
+        return Some(entry.offset);
+    }
+
+    let before = |words| &entry.before[context_start(&entry.before, entry.before.len(), words)..];
+    let after = |words| &entry.after[..context_end(&entry.after, 0, words)];
+
+    let both_sides = (MIN_CONTEXT_WORDS..=CONTEXT_WORDS)
+        .rev()
+        .map(|words| (before(words), after(words)))
+        .filter(|(before, after)| word_count(before) + word_count(after) >= MIN_CONTEXT_WORDS);
+    let one_side = (ONE_SIDED_WORDS..=CONTEXT_WORDS).rev().flat_map(|words| {
+        [(before(words), after(1)), (before(1), after(words))]
+            .into_iter()
+            .filter(|&(before, after)| word_count(before).max(word_count(after)) >= ONE_SIDED_WORDS)
+    });
+
+    for (before, after) in both_sides.chain(one_side) {
+        match unique(text, entry, before, after) {
+            Match::One(position) => return Some(position),
+            Match::Several => return None,
+            Match::None => {}
+        }
+    }
+    None
+}
+
+/// How often a context occurs in the text
+enum Match {
+    None,
+    One(usize),
+    Several,
+}
+
+/// Position of the entry's replacement between `before` and `after`, if they
+/// occur exactly once in `text` and respect the start or end of the document
+/// the entry was next to
+fn unique(text: &str, entry: &SidecarEntry, before: &str, after: &str) -> Match {
+    let replacement = entry.replacement.as_str();
+    let needle = format!("{}{}{}", before, replacement, after);
+    let mut positions = text
+        .match_indices(&needle)
+        .map(|(i, _)| i + before.len())
+        .filter(|&position| !entry.before.is_empty() || position == 0)
+        .filter(|&position| !entry.after.is_empty() || position + replacement.len() == text.len());
+    match (positions.next(), positions.next()) {
+        (None, _) => Match::None,
+        (Some(position), None) => Match::One(position),
+        (Some(_), Some(_)) => Match::Several,
+    }
+}
+
+/// Words with letters or digits in `text`
+fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Start of the `words` words before `offset`
+fn context_start(text: &str, offset: usize, words: usize) -> usize {
+    let mut start = offset;
+    for _ in 0..words {
+        let trimmed = text[..start].trim_end();
+        if trimmed.is_empty() {
+            return 0;
+        }
+        start = trimmed.rfind(char::is_whitespace).map_or(0, |i| {
+            i + trimmed[i..].chars().next().map_or(1, char::len_utf8)
+        });
+    }
+    start
+}
+
+/// End of the `words` words after `offset`
+fn context_end(text: &str, offset: usize, words: usize) -> usize {
+    let mut end = offset;
+    for _ in 0..words {
+        let rest = &text[end..];
+        let word_start = end + (rest.len() - rest.trim_start().len());
+        if word_start == text.len() {
+            return text.len();
+        }
+        end = text[word_start..]
+            .find(char::is_whitespace)
+            .map_or(text.len(), |i| word_start + i);
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CitationRemover, RemoverConfig, Replacement};
+
+    const INPUT: &str = "# Report\n\nSales grew 40% last year[1]. Costs fell [2] sharply.\n\nMore text  here.\n\n## Sources\n[1]: https://a.com\n[2]: https://b.com\n";
+
+    #[test]
+    fn test_round_trip_is_exact() {
+        let (output, sidecar) = CitationRemover::new().remove_reversible(INPUT);
+        let restored = sidecar.restore(&output);
+        assert_eq!(restored.text, INPUT);
+        assert!(restored.unplaced.is_empty());
+
+        let kinds: Vec<_> = sidecar.entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SidecarEntryKind::Marker,
+                SidecarEntryKind::Marker,
+                SidecarEntryKind::Whitespace,
+                SidecarEntryKind::Reference
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reanchors_after_edits() {
+        let (output, sidecar) = CitationRemover::new().remove_reversible(INPUT);
+        let edited = output
+            .replace("Sales grew 40%", "Revenue rose 40%")
+            .replace("More text here.", "Brand new paragraph.\n\nMore text here.");
+        let restored = sidecar.restore(&edited);
+
+        assert!(restored
+            .text
+            .contains("Revenue rose 40% last year[1]. Costs fell [2] sharply."));
+        assert!(restored
+            .text
+            .ends_with("## Sources\n[1]: https://a.com\n[2]: https://b.com\n"));
+        assert!(restored.unplaced.is_empty());
+    }
+
+    #[test]
+    fn test_reports_unplaced_markers() {
+        let (output, sidecar) = CitationRemover::new().remove_reversible(INPUT);
+        let edited = output.replace("Costs fell sharply.", "Everything else changed.");
+        let restored = sidecar.restore(&edited);
+
+        assert_eq!(restored.unplaced.len(), 1);
+        assert_eq!(restored.unplaced[0].text, "[2] ");
+        assert!(restored
+            .text
+            .contains("last year[1]. Everything else changed."));
+    }
+
+    #[test]
+    fn test_deleted_paragraph_leaves_its_marker_unplaced() {
+        let input = "Alpha beta gamma[1].\n\nDelta epsilon zeta[2].\n\nEta theta iota.";
+        let (output, sidecar) = CitationRemover::new().remove_reversible(input);
+        let edited = output.replace("Delta epsilon zeta.\n\n", "");
+        let restored = sidecar.restore(&edited);
+
+        assert_eq!(restored.text, "Alpha beta gamma[1].\n\nEta theta iota.");
+        let unplaced: Vec<_> = restored.unplaced.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(unplaced, ["[2]"]);
+    }
+
+    #[test]
+    fn test_ambiguous_context_is_unplaced() {
+        let input = "It rained all day[1]. Then something else happened.";
+        let (output, sidecar) = CitationRemover::new().remove_reversible(input);
+        let edited = format!("It rained all day. {}", output.replace("Then", "Later"));
+        let restored = sidecar.restore(&edited);
+
+        assert_eq!(restored.text, edited);
+        assert_eq!(restored.unplaced.len(), 1);
+    }
+
+    #[test]
+    fn test_round_trip_replaces_placeholders() {
+        let config = RemoverConfig {
+            replacement: Replacement::HtmlComment,
+            ..Default::default()
+        };
+        let (output, sidecar) = CitationRemover::with_config(config).remove_reversible(INPUT);
+        assert!(output.contains("last year<!-- cite:1 -->. Costs fell <!-- cite:2 --> sharply."));
+        assert_eq!(sidecar.entries[0].replacement, "<!-- cite:1 -->");
+
+        let edited = output.replace("Sales grew", "Revenue rose");
+        let restored = sidecar.restore(&edited);
+        assert_eq!(restored.text, INPUT.replace("Sales grew", "Revenue rose"));
+        assert!(restored.unplaced.is_empty());
+    }
+
+    #[test]
+    fn test_context_windows() {
+        let text = "one two  three four";
+        assert_eq!(&text[context_start(text, 14, 2)..14], "two  three");
+        assert_eq!(&text[14..context_end(text, 14, 1)], " four");
+        assert_eq!(context_start(text, 3, 5), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::offset_map::OffsetMap;
+
+/// Unit in which [`TextEdit`] ranges are measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum OffsetEncoding {
+    /// UTF-8 byte offsets, as used by Rust strings
+    #[default]
+    Utf8,
+    /// UTF-16 code unit offsets, as used by JavaScript, LSP, Yjs and Automerge
+    Utf16,
+}
+
+/// Replace `range` of the input with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextEdit {
+    /// Range in the input, in the requested [`OffsetEncoding`]
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// Edits turning `input` into `output`, derived from the preserved segments
+///
+/// Every stretch between two preserved segments becomes one edit, so edits
+/// are sorted, non-overlapping and never adjacent.
+pub(crate) fn text_edits(
+    map: &OffsetMap,
+    input: &str,
+    output: &str,
+    encoding: OffsetEncoding,
+) -> Vec<TextEdit> {
+    let mut edits: Vec<TextEdit> = Vec::new();
+    let mut original = 0;
+    let mut produced = 0;
+
+    let ends = map
+        .segments()
+        .iter()
+        .map(|s| (s.original.clone(), s.output.clone()))
+        .chain(std::iter::once((
+            input.len()..input.len(),
+            output.len()..output.len(),
+        )));
+    for (next_original, next_output) in ends {
+        if original < next_original.start || produced < next_output.start {
+            let edit = TextEdit {
+                range: original..next_original.start,
+                replacement: output[produced..next_output.start].to_string(),
+            };
+            match edits.last_mut() {
+                Some(previous) if edit.replacement.is_empty() => {
+                    if let Some(start) = slide_back(input, edit.range.clone(), previous.range.end) {
+                        debug_assert_eq!(start, previous.range.end);
+                        previous.range.end = edit.range.end - (edit.range.start - start);
+                    } else {
+                        edits.push(edit);
+                    }
+                }
+                _ => edits.push(edit),
+            }
+        }
+        original = next_original.end;
+        produced = next_output.end;
+    }
+
+    if encoding == OffsetEncoding::Utf16 {
+        let mut converter = Utf16Converter::new(input);
+        for edit in &mut edits {
+            edit.range = converter.convert(edit.range.start)..converter.convert(edit.range.end);
+        }
+    }
+    edits
+}
+
+/// Start of a deletion slid back to `limit`, if that removes the same text
+///
+/// A deletion can move back one character whenever the character before it
+/// equals its last character; e.g. in `a[1]  b`, deleting the second space is
+/// the same as deleting the first, which joins it to the deletion of `[1]`.
+fn slide_back(input: &str, mut range: Range<usize>, limit: usize) -> Option<usize> {
+    while range.start > limit {
+        let before = input[..range.start].chars().next_back()?;
+        let last = input[range.clone()].chars().next_back()?;
+        if before != last {
+            return None;
+        }
+        range = range.start - before.len_utf8()..range.end - before.len_utf8();
+    }
+    Some(range.start)
+}
+
+/// Converts increasing byte offsets to UTF-16 offsets in one pass
+struct Utf16Converter<'a> {
+    text: &'a str,
+    byte: usize,
+    utf16: usize,
+}
+
+impl<'a> Utf16Converter<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            byte: 0,
+            utf16: 0,
+        }
+    }
+
+    fn convert(&mut self, offset: usize) -> usize {
+        self.utf16 += self.text[self.byte..offset].encode_utf16().count();
+        self.byte = offset;
+        self.utf16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CitationRemover;
+
+    fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let mut result = text.to_string();
+        for edit in edits.iter().rev() {
+            result.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        result
+    }
+
+    #[test]
+    fn test_edits_reproduce_output() {
+        let remover = CitationRemover::new();
+        let input = "Text[1]  here[2].   \n\n\n\n[1]: https://a.com\n[2]: https://b.com\n";
+        let edits = remover.text_edits(input, OffsetEncoding::Utf8);
+
+        assert_eq!(apply(input, &edits), remover.remove(input));
+        let ranges: Vec<_> = edits.iter().map(|e| &input[e.range.clone()]).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "[1] ",
+                "[2]",
+                "   \n\n\n\n[1]: https://a.com\n[2]: https://b.com\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let remover = CitationRemover::new();
+        let input = "Café 🚀[1] ok.";
+        let edits = remover.text_edits(input, OffsetEncoding::Utf16);
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                range: 7..10,
+                replacement: String::new()
+            }]
+        );
+
+        let utf16: Vec<u16> = input.encode_utf16().collect();
+        assert_eq!(String::from_utf16_lossy(&utf16[7..10]), "[1]");
+    }
+}
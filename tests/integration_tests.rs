@@ -1,5 +1,11 @@
 use markdown_ai_cite_remove::{
-    remove_citations, remove_citations_with_config, CitationRemover, RemoverConfig,
+    convert_citations, detect_source, export_sources, extract_citations, normalize_citations,
+    remove_citations, remove_citations_auto, remove_citations_with_config,
+    remove_citations_with_report, renumber_citations, restore_citations, Artifact, BuiltinStage,
+    CitationFormat, CitationMatch, CitationRemover, CitationStyle, FnStage, Language, LinkStyle,
+    MarkerPlacement, NormalizeOptions, OffsetEncoding, Pattern, Pipeline, Provider,
+    RemovalCategory, RemovalMode, RemoverConfig, Replacement, RuleId, SourcesFormat, StageContext,
+    StageEdit,
 };
 
 #[test]
@@ -143,6 +149,7 @@ fn test_custom_config_all_disabled() {
         normalize_whitespace: false,
         remove_blank_lines: false,
         trim_lines: false,
+        ..Default::default()
     };
     let input = "Text[1].\n\n[1]: https://example.com";
     let cleaned = remove_citations_with_config(input, config);
@@ -472,7 +479,7 @@ fn test_reference_with_title() {
 fn test_multiple_reference_sections() {
     let input =
         "Content.\n\n## References\n[1]: https://example.com\n\n## Sources\n[2]: https://test.com";
-    // Once we find the first reference section, we remove everything from that point onward
+    // Each reference section is removed together with its body
     let expected = "Content.";
     assert_eq!(remove_citations(input).trim(), expected);
 }
@@ -622,3 +629,658 @@ fn test_real_world_perplexity_markdown_links() {
     assert!(result.contains("Rust Installation"));
     assert!(result.contains("cargo install markdown-ai-cite-remove"));
 }
+
+#[test]
+fn test_multiline_footnote_definition_mid_document() {
+    let input = "Intro[^1].\n\n[^1]: A note that wraps\nlazily onto a second line.\n\n    And has a second paragraph.\n\nMore content.";
+    let cleaned = remove_citations(input);
+    assert_eq!(cleaned, "Intro.\n\nMore content.");
+}
+
+#[test]
+fn test_reference_definition_title_on_next_line() {
+    let input =
+        "Content[1].\n\n[1]: https://example.com\n    \"Example Title\"\n\nClosing paragraph.";
+    let cleaned = remove_citations(input);
+    assert_eq!(cleaned, "Content.\n\nClosing paragraph.");
+}
+
+#[test]
+fn test_multiline_definitions_kept_or_removed_completely() {
+    let config = RemoverConfig {
+        remove_inline_citations: false,
+        remove_reference_links: false,
+        remove_reference_headers: false,
+        normalize_whitespace: false,
+        ..Default::default()
+    };
+    let input =
+        "Text.\n\n[^1]: First line\n    continued.\n\n[2] Author. Title.\n    Journal of Things.";
+    let cleaned = remove_citations_with_config(input, config);
+    assert_eq!(cleaned.trim(), "Text.\n\n[^1]: First line\n    continued.");
+}
+
+#[test]
+fn test_perplexity_export_follow_up_answer_preserved() {
+    let input = include_str!("fixtures/perplexity_export_syntax.md");
+    let cleaned = remove_citations(input);
+    assert!(cleaned.contains("## Overview of the Problems"));
+    assert!(!cleaned.contains("[^1_56]: https://www.macworld.com"));
+}
+
+#[test]
+fn test_lenticular_citations_removed() {
+    let input = "Revenue grew 40%【3†source】 last year【4:0†report.pdf】.";
+    assert_eq!(remove_citations(input), "Revenue grew 40% last year.");
+}
+
+#[test]
+fn test_convert_to_footnotes() {
+    let input = "Claim one[1] and two[source:2].\n\n## References\n[1]: https://a.com \"Alpha\"\n[2]: https://b.com";
+    let output = convert_citations(input, CitationFormat::Footnotes);
+    assert_eq!(
+        output,
+        "Claim one[^1] and two[^2].\n\n[^1]: [Alpha](https://a.com)\n[^2]: <https://b.com>"
+    );
+}
+
+#[test]
+fn test_convert_perplexity_export_to_footnotes() {
+    let input = include_str!("fixtures/perplexity_export_syntax.md");
+    let output = convert_citations(input, CitationFormat::Footnotes);
+    assert!(!output.contains("[^1_1]"));
+    assert!(!output.contains("[^2_21]"));
+    assert!(output.contains("[^1]: <https://"));
+    assert!(output.contains("## Overview of the Problems"));
+}
+
+#[test]
+fn test_convert_to_inline_links() {
+    let input = "Sales grew 40%[1].\n\n## Sources\n[1]: https://www.reuters.com/markets";
+    let output = convert_citations(input, CitationFormat::InlineLinks(LinkStyle::Parenthesized));
+    assert_eq!(
+        output.trim(),
+        "Sales grew 40% ([reuters.com](https://www.reuters.com/markets))."
+    );
+}
+
+#[test]
+fn test_convert_chatgpt_fixture_to_inline_links() {
+    let input = include_str!("fixtures/chatgpt.md");
+    let output = convert_citations(input, CitationFormat::InlineLinks(LinkStyle::Parenthesized));
+    assert!(output.contains("([Apple Podcasts](https://podcasters.apple.com/support/832-podcast-metadata?utm_source=chatgpt.com))"));
+    assert!(!output.contains("[1]: https://podcasts.apple.com"));
+}
+
+#[test]
+fn test_renumber_after_partial_removal() {
+    let input = "Growth[1] slowed[4] sharply[7].\n\n[1]: https://a.com\n[4]: https://b.com\n[7]: https://www.a.com/";
+    let output = renumber_citations(input);
+    assert_eq!(
+        output,
+        "Growth[1] slowed[2] sharply[1].\n\n[1]: https://a.com\n[2]: https://b.com"
+    );
+}
+
+#[test]
+fn test_renumber_perplexity_export_fixture() {
+    let input = include_str!("fixtures/perplexity_export_syntax.md");
+    let output = renumber_citations(input);
+    assert!(!output.contains("[^1_1]"));
+    assert!(!output.contains("[^2_21]"));
+    assert!(output.contains("[^1]: https://"));
+}
+
+#[test]
+fn test_named_citation_with_space_removed() {
+    assert_eq!(remove_citations("Claim[cite: 5]."), "Claim.");
+}
+
+#[test]
+fn test_normalize_mixed_vendor_syntaxes() {
+    let input = "One[1]. Two[^1_3]. Three【2†source】. Four[source:4]. Five[cite: 5].\n\n[1]: https://one.com\n[^1_3]: https://three.com\n[4]: https://four.com";
+    let options = NormalizeOptions {
+        style: CitationStyle::Numeric,
+        placement: MarkerPlacement::AfterPunctuation,
+    };
+    let output = normalize_citations(input, options);
+    assert_eq!(
+        output,
+        "One.[1] Two.[2] Three.[4] Four.[3] Five.[5]\n\n[1]: https://one.com\n[2]: https://three.com\n[3]: https://four.com"
+    );
+}
+
+#[test]
+fn test_extract_citations_from_chatgpt_fixture() {
+    let input = include_str!("fixtures/chatgpt.md");
+    let analysis = extract_citations(input);
+
+    assert_eq!(analysis.sources.len(), 7);
+    assert_eq!(analysis.unused().count(), 0);
+    assert!(analysis.citations.iter().all(|c| c.is_resolved()));
+
+    let apple = &analysis.sources[3];
+    assert_eq!(apple.label, "4");
+    assert_eq!(apple.title.as_deref(), Some("Update your podcast metadata"));
+    assert_eq!(
+        &input[analysis.citations[0].range.clone()],
+        format!("[{}]", analysis.citations[0].id)
+    );
+}
+
+#[test]
+fn test_export_sources_from_chatgpt_fixture() {
+    let input = include_str!("fixtures/chatgpt.md");
+    let analysis = extract_citations(input);
+
+    let csl = export_sources(
+        &analysis.sources,
+        SourcesFormat::CslJson,
+        Some("2025-01-31"),
+    );
+    assert_eq!(csl.matches("\"type\": \"webpage\"").count(), 7);
+    assert!(csl.contains("\"title\": \"Update your podcast metadata\""));
+
+    let bibtex = export_sources(&analysis.sources, SourcesFormat::BibTex, Some("2025-01-31"));
+    assert_eq!(bibtex.matches("@online{").count(), 7);
+    assert_eq!(bibtex.matches("urldate = {2025-01-31}").count(), 7);
+
+    let ris = export_sources(&analysis.sources, SourcesFormat::Ris, None);
+    assert_eq!(ris.matches("TY  - ELEC").count(), 7);
+    assert_eq!(ris.matches("ER  - ").count(), 7);
+}
+
+#[test]
+fn test_removal_report_matches_remove() {
+    for input in [
+        include_str!("fixtures/chatgpt.md"),
+        include_str!("fixtures/perplexity.md"),
+        include_str!("fixtures/perplexity_export_syntax.md"),
+    ] {
+        let (output, report) = remove_citations_with_report(input);
+        assert_eq!(output, remove_citations(input));
+        assert_eq!(report.output_bytes, output.len());
+        assert!(report.counts.inline > 0);
+
+        for removal in report.removals.iter() {
+            if removal.rule.category() != RemovalCategory::Whitespace {
+                assert_eq!(&input[removal.range.clone()], removal.text);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_offset_map_segments_copy_input_for_every_config() {
+    let configs = [
+        RemoverConfig::default(),
+        RemoverConfig::inline_only(),
+        RemoverConfig::references_only(),
+        RemoverConfig {
+            normalize_whitespace: false,
+            ..Default::default()
+        },
+    ];
+    for input in [
+        include_str!("fixtures/chatgpt.md"),
+        include_str!("fixtures/perplexity.md"),
+        include_str!("fixtures/perplexity_export_syntax.md"),
+        include_str!("fixtures/matthew_rust_install.md"),
+    ] {
+        for config in configs.iter().cloned() {
+            let remover = CitationRemover::with_config(config);
+            let (output, map) = remover.remove_with_offset_map(input);
+            assert_eq!(output, remover.remove(input));
+
+            let mut covered = 0;
+            for segment in map.segments() {
+                assert_eq!(segment.output.start, covered);
+                assert_eq!(
+                    &input[segment.original.clone()],
+                    &output[segment.output.clone()]
+                );
+                assert_eq!(
+                    map.output_offset(segment.original.start),
+                    Some(segment.output.start)
+                );
+                assert_eq!(
+                    map.original_offset(segment.output.start),
+                    segment.original.start
+                );
+                covered = segment.output.end;
+            }
+            assert_eq!(covered, output.len());
+        }
+    }
+}
+
+#[test]
+fn test_text_edits_reproduce_output_in_both_encodings() {
+    let remover = CitationRemover::new();
+    for input in [
+        include_str!("fixtures/chatgpt.md"),
+        include_str!("fixtures/perplexity.md"),
+        include_str!("fixtures/perplexity_export_syntax.md"),
+        "Zürich 🏔️[1]  is high【2†source】.\n\n[1]: https://ch.ch",
+    ] {
+        let expected = remover.remove(input);
+
+        let mut bytes = input.to_string();
+        for edit in remover.text_edits(input, OffsetEncoding::Utf8).iter().rev() {
+            bytes.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        assert_eq!(bytes, expected);
+
+        let mut units: Vec<u16> = input.encode_utf16().collect();
+        for edit in remover
+            .text_edits(input, OffsetEncoding::Utf16)
+            .iter()
+            .rev()
+        {
+            units.splice(edit.range.clone(), edit.replacement.encode_utf16());
+        }
+        assert_eq!(String::from_utf16(&units).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_reversible_removal_round_trips_fixtures() {
+    let remover = CitationRemover::new();
+    for input in [
+        include_str!("fixtures/chatgpt.md"),
+        include_str!("fixtures/perplexity.md"),
+        include_str!("fixtures/perplexity_export_syntax.md"),
+        include_str!("fixtures/matthew_rust_install.md"),
+    ] {
+        let (output, sidecar) = remover.remove_reversible(input);
+        assert_eq!(output, remover.remove(input));
+
+        let restored = restore_citations(&output, &sidecar);
+        assert_eq!(restored.text, input);
+        assert!(restored.unplaced.is_empty());
+    }
+}
+
+#[test]
+fn test_replacement_strategies() {
+    let input = "Rust is fast[1] and safe[source:2].\n\n[1]: https://rust-lang.org\n[2]: https://doc.rust-lang.org";
+    let remove = |replacement| {
+        let config = RemoverConfig {
+            replacement,
+            ..Default::default()
+        };
+        CitationRemover::with_config(config).remove(input)
+    };
+
+    assert_eq!(remove(Replacement::Delete), "Rust is fast and safe.");
+    assert_eq!(
+        remove(Replacement::Text("†".to_string())),
+        "Rust is fast† and safe†."
+    );
+    assert_eq!(
+        remove(Replacement::HtmlComment),
+        "Rust is fast<!-- cite:1 --> and safe<!-- cite:source:2 -->."
+    );
+    assert_eq!(
+        remove(Replacement::custom(|citation: &CitationMatch| {
+            let url = citation.source.and_then(|s| s.url.as_deref());
+            format!("[{}]({})", citation.id, url.unwrap_or("#"))
+        })),
+        "Rust is fast[1](https://rust-lang.org) and safe[2](https://doc.rust-lang.org)."
+    );
+}
+
+#[test]
+fn test_builder_without_custom_patterns_matches_default() {
+    let remover = CitationRemover::builder().build().unwrap();
+    let input = include_str!("fixtures/chatgpt.md");
+    assert_eq!(remover.remove(input), remove_citations(input));
+}
+
+#[test]
+fn test_custom_patterns_with_replacement() {
+    let remover = CitationRemover::builder()
+        .config(RemoverConfig {
+            replacement: Replacement::HtmlComment,
+            ..Default::default()
+        })
+        .inline_pattern(Pattern::regex(r"\(\((?P<id>\d+)\)\)"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        remover.remove("Claim((7)) and fact[1]."),
+        "Claim<!-- cite:7 --> and fact<!-- cite:1 -->."
+    );
+
+    let invalid = CitationRemover::builder()
+        .header_pattern(Pattern::regex("Sources("))
+        .build();
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn test_language_packs_and_named_prefixes() {
+    let config = RemoverConfig::default()
+        .with_named_prefix("citation")
+        .with_language(Language::German)
+        .with_language(Language::Spanish)
+        .with_language(Language::Chinese);
+    let remover = CitationRemover::with_config(config);
+
+    assert_eq!(
+        remover.remove("Berlin ist groß[citation:1].\n\n## Quellen\n- https://berlin.de\n"),
+        "Berlin ist groß.\n"
+    );
+    assert_eq!(
+        remover.remove("Madrid[2] es grande.\n\n## Referencias\n[2]: https://madrid.es\n"),
+        "Madrid es grande.\n"
+    );
+    assert_eq!(
+        remover.remove("北京是中国的首都 [citation:3] 。\n\n## 参考文献\n1. 维基百科\n"),
+        "北京是中国的首都。\n"
+    );
+
+    // The defaults still only know English headings and prefixes
+    let input = "Fact[citation:1].\n\n## Quellen\n- https://berlin.de";
+    assert_eq!(remove_citations(input), input);
+}
+
+#[test]
+fn test_replacements_keep_offsets_and_edits_consistent() {
+    let config = RemoverConfig {
+        replacement: Replacement::HtmlComment,
+        ..Default::default()
+    };
+    let remover = CitationRemover::with_config(config);
+    let input = include_str!("fixtures/perplexity_export_syntax.md");
+
+    let (output, report) = remover.remove_with_report(input);
+    assert_eq!(output, remover.remove(input));
+    assert!(report
+        .by_category(RemovalCategory::Inline)
+        .all(|r| r.replacement.starts_with("<!-- cite:")));
+
+    let (_, map) = remover.remove_with_offset_map(input);
+    for segment in map.segments() {
+        assert_eq!(
+            &input[segment.original.clone()],
+            &output[segment.output.clone()]
+        );
+    }
+
+    let mut applied = input.to_string();
+    for edit in remover.text_edits(input, OffsetEncoding::Utf8).iter().rev() {
+        applied.replace_range(edit.range.clone(), &edit.replacement);
+    }
+    assert_eq!(applied, output);
+
+    let (_, sidecar) = remover.remove_reversible(input);
+    assert_eq!(restore_citations(&output, &sidecar).text, input);
+}
+
+#[test]
+fn test_custom_pipeline_stage() {
+    let strip_src = FnStage::new("strip-src", |text: &str, context: &StageContext| {
+        text.match_indices("{{src}}")
+            .map(|(start, m)| start..start + m.len())
+            .filter(|range| !context.is_protected(range.clone()))
+            .map(|range| StageEdit::delete(range, RuleId::custom("strip-src")))
+            .collect()
+    });
+    let mut pipeline = Pipeline::standard();
+    pipeline.insert_after(BuiltinStage::References.name(), strip_src);
+    assert_eq!(
+        pipeline.names(),
+        [
+            "artifacts",
+            "references",
+            "strip-src",
+            "inline-citations",
+            "whitespace",
+            "blank-lines",
+            "trim-lines"
+        ]
+    );
+
+    let remover = CitationRemover::builder()
+        .pipeline(pipeline)
+        .build()
+        .unwrap();
+    let input = "Fact{{src}}[1] and `{{src}}`.\n\n[1]: https://a.com";
+    let (output, report) = remover.remove_with_report(input);
+    assert_eq!(output, "Fact and `{{src}}`.");
+    assert_eq!(report.counts.custom, 1);
+    let custom: Vec<_> = report.by_category(RemovalCategory::Custom).collect();
+    assert_eq!(custom[0].rule, RuleId::custom("strip-src"));
+    assert_eq!(custom[0].range, 4..11);
+
+    let (_, sidecar) = remover.remove_reversible(input);
+    assert_eq!(restore_citations(&output, &sidecar).text, input);
+
+    // Without the inline stage, markers stay
+    let mut pipeline = Pipeline::standard();
+    assert!(pipeline.remove(BuiltinStage::InlineCitations.name()));
+    let remover = CitationRemover::builder()
+        .pipeline(pipeline)
+        .build()
+        .unwrap();
+    assert_eq!(remover.remove(input), "Fact{{src}}[1] and `{{src}}`.");
+}
+
+#[test]
+fn test_provider_profiles() {
+    let clean = |provider, input: &str| {
+        CitationRemover::with_config(RemoverConfig::for_provider(provider)).remove(input)
+    };
+
+    assert_eq!(
+        clean(
+            Provider::ChatGpt,
+            "Rust is fast. :contentReference[oaicite:1]{index=1} It is safe citeturn0search2.\n\nSee [the book](https://doc.rust-lang.org/book/?utm_source=chatgpt.com)."
+        ),
+        "Rust is fast. It is safe.\n\nSee [the book](https://doc.rust-lang.org/book/)."
+    );
+    assert_eq!(
+        clean(Provider::Gemini, "[cite_start]Rust is fast.[cite: 1, 3]"),
+        "Rust is fast."
+    );
+    assert_eq!(
+        clean(
+            Provider::Copilot,
+            "Rust is fast[^1^].\n\nSource: Conversation with Copilot, 6/3/2024\n(1) Rust. https://rust-lang.org."
+        ),
+        "Rust is fast."
+    );
+    assert_eq!(
+        clean(Provider::DeepSeek, "Rust is fast[citation:2]."),
+        "Rust is fast."
+    );
+
+    let export = include_str!("fixtures/perplexity_export_syntax.md");
+    assert!(remove_citations(export).contains("r2cdn.perplexity.ai"));
+    let output = clean(Provider::PerplexityExport, export);
+    assert!(output.starts_with("# I started getting"));
+
+    // Artifacts in code are left alone
+    let code = "Use `:contentReference[oaicite:0]` in tests.";
+    assert_eq!(clean(Provider::ChatGpt, code), code);
+
+    // Profiles without artifacts behave like the default configuration
+    let input = include_str!("fixtures/perplexity.md");
+    assert_eq!(clean(Provider::Perplexity, input), remove_citations(input));
+    assert_eq!(clean(Provider::Claude, input), remove_citations(input));
+}
+
+#[test]
+fn test_removal_mode_scopes_a_profile() {
+    let input = "Fact[1] :contentReference[oaicite:0]{index=0}.\n\n## Sources\n- https://a.com";
+    let config = RemoverConfig::for_provider(Provider::ChatGpt);
+
+    let inline = CitationRemover::with_config(config.clone().with_mode(RemovalMode::InlineOnly));
+    assert_eq!(inline.remove(input), "Fact.\n\n## Sources\n- https://a.com");
+
+    // The inline artifact stays with the inline citations
+    let references =
+        CitationRemover::with_config(config.clone().with_mode(RemovalMode::ReferencesOnly));
+    assert_eq!(
+        references.remove(input),
+        "Fact[1] :contentReference[oaicite:0]{index=0}."
+    );
+
+    let (_, report) = CitationRemover::with_config(config).remove_with_report(input);
+    assert_eq!(report.counts.artifacts, 1);
+    assert_eq!(report.counts.citations(), 3);
+    assert!(report
+        .by_rule(RuleId::Artifact(Artifact::ContentReference))
+        .any(|r| r.text == " :contentReference[oaicite:0]{index=0}"));
+}
+
+#[test]
+fn test_removal_mode_scopes_reference_artifacts() {
+    let input =
+        "Fact[^1^].\n\nSource: Conversation with Copilot, 5/1/2025\n(1) Title. https://a.com\n";
+    let copilot = RemoverConfig::for_provider(Provider::Copilot);
+
+    let inline = CitationRemover::with_config(copilot.clone().with_mode(RemovalMode::InlineOnly));
+    assert_eq!(
+        inline.remove(input),
+        "Fact.\n\nSource: Conversation with Copilot, 5/1/2025\n(1) Title. https://a.com\n"
+    );
+    let references =
+        CitationRemover::with_config(copilot.clone().with_mode(RemovalMode::ReferencesOnly));
+    assert_eq!(references.remove(input), "Fact[^1^].\n");
+
+    // The mode holds whichever is applied first
+    let config = RemoverConfig::inline_only().with_provider(Provider::Copilot);
+    assert_eq!(config.artifacts, [Artifact::CopilotFootnotes]);
+}
+
+#[test]
+fn test_removal_mode_keeps_whitespace_settings() {
+    let config = RemoverConfig {
+        remove_blank_lines: false,
+        trim_lines: false,
+        ..Default::default()
+    };
+    for mode in [RemovalMode::All, RemovalMode::ReferencesOnly] {
+        let scoped = config.clone().with_mode(mode);
+        assert!(!scoped.remove_blank_lines && !scoped.trim_lines);
+    }
+    assert!(
+        RemoverConfig::default()
+            .with_mode(RemovalMode::InlineOnly)
+            .remove_blank_lines
+    );
+}
+
+#[test]
+fn test_detect_source_on_fixtures() {
+    let top = |input: &str| detect_source(input).first().map(|d| d.provider);
+    assert_eq!(
+        top(include_str!("fixtures/chatgpt.md")),
+        Some(Provider::ChatGpt)
+    );
+    assert_eq!(
+        top(include_str!("fixtures/perplexity.md")),
+        Some(Provider::Perplexity)
+    );
+    assert_eq!(
+        Provider::detect(include_str!("fixtures/perplexity_export_syntax.md")),
+        Some(Provider::PerplexityExport)
+    );
+
+    let detections = detect_source(include_str!("fixtures/perplexity_export_syntax.md"));
+    assert!(detections
+        .windows(2)
+        .all(|w| w[0].confidence >= w[1].confidence));
+    assert!(detections
+        .iter()
+        .all(|d| d.confidence > 0.0 && d.confidence <= 1.0));
+}
+
+#[test]
+fn test_auto_mode_applies_detected_profile() {
+    let input = "Rust is fast[citation:1].\n\nIt is safe[citation:2].";
+    assert_eq!(Provider::detect(input), Some(Provider::DeepSeek));
+    assert_eq!(remove_citations_auto(input), "Rust is fast.\n\nIt is safe.");
+    assert_eq!(remove_citations(input), input);
+
+    // Nothing detected: same as the default configuration
+    let input = "Fact[1].\n\n[1]: https://a.com";
+    assert_eq!(Provider::detect(input), None);
+    assert_eq!(remove_citations_auto(input), remove_citations(input));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_sidecar_serializes_to_json() {
+    let input = "Rust is fast[1] and memory safe[^2].\n\n[1]: https://a.com\n[^2]: Note";
+    let (output, sidecar) = CitationRemover::new().remove_reversible(input);
+
+    let json = serde_json::to_string(&sidecar).unwrap();
+    assert!(json.contains("\"kind\":\"marker\""));
+    let loaded: markdown_ai_cite_remove::CitationSidecar = serde_json::from_str(&json).unwrap();
+
+    let edited = output.replace("Rust", "Rust really");
+    let restored = restore_citations(&edited, &loaded);
+    assert_eq!(
+        restored.text,
+        "Rust really is fast[1] and memory safe[^2].\n\n[1]: https://a.com\n[^2]: Note"
+    );
+    assert!(restored.unplaced.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_file_discovery_and_profiles() {
+    use markdown_ai_cite_remove::ConfigFile;
+
+    let root = std::env::temp_dir().join(format!("mdcr-config-{}", std::process::id()));
+    let nested = root.join("docs").join("notes");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(
+        root.join(ConfigFile::FILE_NAME),
+        "provider = \"gemini\"\n\n[profiles.placeholders]\nreplacement = { text = \"†\" }\n",
+    )
+    .unwrap();
+
+    let path = ConfigFile::find(&nested).unwrap();
+    assert_eq!(path, root.join(ConfigFile::FILE_NAME));
+    let file = ConfigFile::load(&path).unwrap();
+
+    let input = "[cite_start]Rust is fast.[cite: 1]";
+    let base = remove_citations_with_config(input, file.config(None).unwrap());
+    assert_eq!(base, "Rust is fast.");
+    let placeholders = file.config(Some("placeholders")).unwrap();
+    assert_eq!(
+        remove_citations_with_config("Fact[1].", placeholders),
+        "Fact†."
+    );
+    assert!(file.config(Some("missing")).is_err());
+
+    std::fs::write(root.join("config.json"), r#"{"mode": "references-only"}"#).unwrap();
+    let config = RemoverConfig::from_file(root.join("config.json")).unwrap();
+    assert!(!config.remove_inline_citations);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_report_round_trips_through_json() {
+    let input = "Fact[1] and [source:2].\n\n[1]: https://a.com";
+    let (_, report) = remove_citations_with_report(input);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains("\"rule\":\"inline-numeric\""));
+    let loaded: markdown_ai_cite_remove::RemovalReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.counts, report.counts);
+    assert_eq!(
+        loaded.removals.iter().map(|r| &r.rule).collect::<Vec<_>>(),
+        report.removals.iter().map(|r| &r.rule).collect::<Vec<_>>()
+    );
+
+    let custom: RuleId = serde_json::from_str("\"todo-marker\"").unwrap();
+    assert_eq!(custom, RuleId::custom("todo-marker"));
+    assert_eq!(custom.as_str(), "todo-marker");
+}